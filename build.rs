@@ -1,7 +1,17 @@
 use glass_build::{cargo_build_example, clang_build_example, generate_bindings};
 
 const HTTP_WITX: &str = "crates/engine/test/http/deislabs_http_v01.witx";
+const HTTP_V02_WITX: &str = "crates/engine/test/http/deislabs_http_v02.witx";
+const HTTP_STREAM_WITX: &str = "crates/engine/test/http/deislabs_http_stream_v01.witx";
+const HTTP_STREAM_HOST_WITX: &str = "crates/engine/test/http/deislabs_http_stream_host_v01.witx";
 const HTTP_TESTS_DIR: &str = "crates/engine/test/http/tests";
+const HTTP_RUST_TESTS: &[&str] = &[
+    "rust",
+    "rust-v02",
+    "rust-stream",
+    "wagi",
+    "wagi-capabilities",
+];
 
 const PING_WITX: &str = "crates/engine/test/http/deislabs_ping_v01.witx";
 const PING_TESTS_DIR: &str = "crates/engine/test/ping/tests";
@@ -15,8 +25,17 @@ fn main() {
 
 fn build_http_tests() {
     println!("cargo:rerun-if-changed={}", HTTP_WITX);
-    println!("cargo:rerun-if-changed={}/rust/lib.rs", HTTP_TESTS_DIR);
+    println!("cargo:rerun-if-changed={}", HTTP_V02_WITX);
+    println!("cargo:rerun-if-changed={}", HTTP_STREAM_WITX);
+    println!("cargo:rerun-if-changed={}", HTTP_STREAM_HOST_WITX);
     println!("cargo:rerun-if-changed={}/c/lib.c", HTTP_TESTS_DIR);
+    for example in HTTP_RUST_TESTS {
+        println!("cargo:rerun-if-changed={}/{}/src", HTTP_TESTS_DIR, example);
+        println!(
+            "cargo:rerun-if-changed={}/{}/Cargo.toml",
+            HTTP_TESTS_DIR, example
+        );
+    }
 
     generate_bindings(
         "c",
@@ -26,7 +45,9 @@ fn build_http_tests() {
     )
     .unwrap();
     clang_build_example(HTTP_TESTS_DIR, "c").unwrap();
-    cargo_build_example(HTTP_TESTS_DIR, "rust").unwrap();
+    for example in HTTP_RUST_TESTS {
+        cargo_build_example(HTTP_TESTS_DIR, example).unwrap();
+    }
 }
 
 fn build_ping_tests() {
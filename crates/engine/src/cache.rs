@@ -0,0 +1,95 @@
+use anyhow::{Context, Error};
+use sha2::{Digest, Sha256};
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
+use tempfile::NamedTempFile;
+use wasmtime::{Engine, Module};
+
+/// The smallest valid module, precompiled to fingerprint the engine.
+const EMPTY_MODULE: &[u8] = b"\0asm\x01\0\0\0";
+
+/// On-disk cache of compiled modules.
+///
+/// Entries are keyed by the SHA-256 digest of the module and a fingerprint of
+/// the engine, which is the digest of an empty module precompiled by it. As
+/// Wasmtime embeds its version and code generation settings in the artifacts
+/// it produces, changing any of them invalidates previously compiled
+/// artifacts. Wasmtime also refuses to load artifacts compiled with
+/// incompatible settings, in which case the module is compiled again.
+pub struct ModuleCache {
+    dir: PathBuf,
+    fingerprint: String,
+}
+
+impl ModuleCache {
+    /// Create a cache in `dir` for modules compiled by `engine`.
+    pub fn new(dir: impl Into<PathBuf>, engine: &Engine) -> Result<Self, Error> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)
+            .with_context(|| format!("failed to create cache directory '{}'", dir.display()))?;
+
+        Ok(Self {
+            dir,
+            fingerprint: Self::fingerprint(engine)?,
+        })
+    }
+
+    /// Get the compiled module for `bytes`, compiling and caching it on a miss.
+    pub fn load(&self, engine: &Engine, bytes: &[u8]) -> Result<Module, Error> {
+        let digest = format!("{:x}", Sha256::digest(bytes));
+        let path = self
+            .dir
+            .join(format!("{}.{}.cwasm", digest, self.fingerprint));
+
+        if path.exists() {
+            match Self::deserialize(engine, &path) {
+                Ok(module) => {
+                    log::debug!("Loaded compiled module from cache: {}", path.display());
+                    return Ok(module);
+                }
+                Err(e) => log::warn!("Ignoring unusable cache entry {}: {:#}", path.display(), e),
+            }
+        }
+
+        let module = Module::new(engine, bytes)?;
+        if let Err(e) = self.store(&module, &path) {
+            log::warn!("Cannot cache compiled module {}: {:#}", path.display(), e);
+        }
+
+        Ok(module)
+    }
+
+    fn deserialize(engine: &Engine, path: &Path) -> Result<Module, Error> {
+        let bytes = fs::read(path)?;
+        // The cache directory is trusted to only contain artifacts
+        // serialized by `ModuleCache::store`.
+        unsafe { Module::deserialize(engine, &bytes) }
+    }
+
+    /// Write the compiled module to the cache.
+    ///
+    /// Entries for the same module compiled by other engines are kept, as
+    /// they may still be used by other processes sharing the cache.
+    fn store(&self, module: &Module, path: &Path) -> Result<(), Error> {
+        // Write to a unique temporary file first so concurrent readers never
+        // observe a partially written artifact, even across processes.
+        let mut tmp = NamedTempFile::new_in(&self.dir)?;
+        tmp.write_all(&module.serialize()?)?;
+        tmp.persist(path)?;
+
+        Ok(())
+    }
+
+    fn fingerprint(engine: &Engine) -> Result<String, Error> {
+        let artifact = engine
+            .precompile_module(EMPTY_MODULE)
+            .context("failed to fingerprint the engine")?;
+        let mut hasher = Sha256::new();
+        hasher.update(format!("glass={}\n", env!("CARGO_PKG_VERSION")));
+        hasher.update(artifact);
+        Ok(format!("{:x}", hasher.finalize())[..16].to_string())
+    }
+}
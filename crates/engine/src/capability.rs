@@ -0,0 +1,129 @@
+use crate::{
+    stdio::{Capture, Stream},
+    Config, Context, Invocation,
+};
+use anyhow::Error;
+use wasi_cap_std_sync::{ambient_authority, Dir, WasiCtxBuilder};
+use wasi_common::{pipe::ReadPipe, WasiCtx};
+use wasi_experimental_http_wasmtime::HttpCtx;
+use wasi_nn_onnx_wasmtime::WasiNnTractCtx;
+use wasmtime::Linker;
+
+/// A host API made available to guests.
+///
+/// Capabilities are added to a `WasiExecutionContextBuilder` with
+/// `with_capability`. Their imports are linked once, and their state is
+/// created for every store and kept in the `Context` extensions, where
+/// the imports look it up by type.
+pub trait HostCapability<T>: Send + Sync {
+    /// Define the imports of the capability in the linker.
+    fn add_to_linker(&self, linker: &mut Linker<Context<T>>) -> Result<(), Error>;
+
+    /// Create the state the imports use in a new store.
+    fn init_store(&self, ctx: &mut Context<T>) -> Result<(), Error>;
+}
+
+/// Core WASI, with the environment, directories and standard streams
+/// configured on the engine, as adjusted by the `Invocation`.
+pub struct Wasi {
+    config: Config,
+}
+
+impl Wasi {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            config: config.clone(),
+        }
+    }
+}
+
+impl<T> HostCapability<T> for Wasi {
+    fn add_to_linker(&self, linker: &mut Linker<Context<T>>) -> Result<(), Error> {
+        wasmtime_wasi::add_to_linker(linker, |host| host.get_mut::<WasiCtx>().unwrap())?;
+        Ok(())
+    }
+
+    fn init_store(&self, ctx: &mut Context<T>) -> Result<(), Error> {
+        let invocation = ctx.remove::<Invocation>().unwrap_or_default();
+        let mut builder = WasiCtxBuilder::new()
+            .envs(&self.config.vars)?
+            .envs(&invocation.envs)?;
+
+        builder = match invocation.stdin {
+            Some(stdin) => builder.stdin(stdin),
+            None if self.config.empty_stdin => builder.stdin(Box::new(ReadPipe::from(Vec::new()))),
+            None => builder.inherit_stdin(),
+        };
+
+        let mut capture = Capture::new(ctx.module());
+        if let Some(runtime_config) = &self.config.runtime_config {
+            capture.redact(runtime_config.values());
+        }
+        builder = match invocation
+            .stdout
+            .or_else(|| capture.file(Stream::Stdout, &self.config.stdout))
+        {
+            Some(stdout) => builder.stdout(stdout),
+            None => builder.inherit_stdout(),
+        };
+        builder = match capture.file(Stream::Stderr, &self.config.stderr) {
+            Some(stderr) => builder.stderr(stderr),
+            None => builder.inherit_stderr(),
+        };
+        // Flushes the captured output to the log when the store is dropped.
+        ctx.insert(capture);
+
+        for (guest, host) in self.config.preopen_dirs.iter() {
+            let dir = anyhow::Context::with_context(
+                Dir::open_ambient_dir(host, ambient_authority()),
+                || format!("failed to open directory '{}'", host),
+            )?;
+            builder = builder.preopened_dir(dir, guest)?;
+        }
+
+        ctx.insert(builder.build());
+        Ok(())
+    }
+}
+
+/// Outbound HTTP requests to the hosts allowed on the engine.
+pub struct ExperimentalHttp {
+    allowed_hosts: Option<Vec<String>>,
+}
+
+impl ExperimentalHttp {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            allowed_hosts: config.allowed_http_hosts.clone(),
+        }
+    }
+}
+
+impl<T> HostCapability<T> for ExperimentalHttp {
+    fn add_to_linker(&self, linker: &mut Linker<Context<T>>) -> Result<(), Error> {
+        HttpCtx::new(self.allowed_hosts.clone(), None)?.add_to_linker(linker)?;
+        Ok(())
+    }
+
+    // The experimental HTTP imports keep no state in the store.
+    fn init_store(&self, _: &mut Context<T>) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+/// WASI NN, using the self-contained ONNX implementation.
+pub struct Nn;
+
+impl<T> HostCapability<T> for Nn {
+    fn add_to_linker(&self, linker: &mut Linker<Context<T>>) -> Result<(), Error> {
+        wasi_nn_onnx_wasmtime::add_to_linker(linker, |host| {
+            host.get_mut::<WasiNnTractCtx>().unwrap()
+        })?;
+        Ok(())
+    }
+
+    fn init_store(&self, ctx: &mut Context<T>) -> Result<(), Error> {
+        ctx.insert(WasiNnTractCtx::default());
+        Ok(())
+    }
+}
@@ -0,0 +1,60 @@
+use crate::{Config, ModuleCache};
+use anyhow::Error;
+use wasmtime::{Engine, Module};
+
+/// Header Wasmtime writes at the start of serialized modules.
+const PRECOMPILED_HEADER: &[u8] = b"\0wasmtime-aot";
+
+/// Check whether `bytes` is an artifact built by `precompile_module`
+/// rather than a WebAssembly module.
+pub fn is_precompiled(bytes: &[u8]) -> bool {
+    bytes.starts_with(PRECOMPILED_HEADER)
+}
+
+/// Compile a WebAssembly module ahead of time for engines using `config`.
+///
+/// The artifact can only be loaded by engines created with the same
+/// configuration and Wasmtime version.
+pub fn precompile_module(config: &Config, bytes: &[u8]) -> Result<Vec<u8>, Error> {
+    let config = config.with_engine_features()?;
+    Engine::new(&config.wasi_config)?.precompile_module(bytes)
+}
+
+/// Load module `name` for an engine created using `config`, which can either be
+/// a WebAssembly module or, for `local` files when `config.allow_precompiled`
+/// is set, an artifact built by `precompile_module`.
+pub(crate) fn load_module(
+    engine: &Engine,
+    config: &Config,
+    name: &str,
+    bytes: &[u8],
+    local: bool,
+) -> Result<Module, Error> {
+    if is_precompiled(bytes) {
+        anyhow::ensure!(
+            local,
+            "'{}' is a precompiled artifact, which can only be loaded from a local file",
+            name
+        );
+        anyhow::ensure!(
+            config.allow_precompiled,
+            "'{}' is a precompiled artifact, which is only loaded when explicitly allowed",
+            name
+        );
+        return deserialize(engine, bytes);
+    }
+
+    match &config.cache_dir {
+        Some(dir) => ModuleCache::new(dir, engine)?.load(engine, bytes),
+        None => Module::new(engine, bytes),
+    }
+}
+
+fn deserialize(engine: &Engine, bytes: &[u8]) -> Result<Module, Error> {
+    // Precompiled artifacts are trusted to come from `precompile_module`,
+    // as they contain native code that is not validated when loading.
+    anyhow::Context::context(
+        unsafe { Module::deserialize(engine, bytes) },
+        "failed to load precompiled module, it may have been compiled with a different configuration",
+    )
+}
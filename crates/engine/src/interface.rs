@@ -0,0 +1,148 @@
+use crate::{compile, Config, ModuleSource};
+use anyhow::Error;
+use wasmparser::{ExternalKind, Parser, Payload};
+use wasmtime::Engine;
+
+/// Magic number at the start of binary WebAssembly modules.
+const WASM_MAGIC: &[u8] = b"\0asm";
+
+/// Interface name that selects the interface based on the module's exports.
+pub const AUTO_INTERFACE: &str = "auto";
+
+/// A WASI interface entrypoint modules can implement, and how to build
+/// an engine executing modules that implement it.
+pub struct Interface<E> {
+    pub name: &'static str,
+    /// Functions a module must export to implement the interface.
+    pub exports: &'static [&'static str],
+    pub build: fn(&ModuleSource, &Config) -> Result<E, Error>,
+}
+
+impl<E> Interface<E> {
+    fn is_implemented_by(&self, exports: &[String]) -> bool {
+        self.exports.iter().all(|e| exports.iter().any(|x| x == e))
+    }
+}
+
+/// The interfaces supported by a trigger, selected by name at runtime.
+pub struct InterfaceRegistry<E> {
+    interfaces: Vec<Interface<E>>,
+}
+
+impl<E> Default for InterfaceRegistry<E> {
+    fn default() -> Self {
+        Self {
+            interfaces: Vec::new(),
+        }
+    }
+}
+
+impl<E> InterfaceRegistry<E> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register an interface. When automatically selecting an interface,
+    /// interfaces are tried in the order they were registered.
+    pub fn register(&mut self, interface: Interface<E>) -> &mut Self {
+        self.interfaces.push(interface);
+        self
+    }
+
+    /// Names of the registered interfaces.
+    pub fn names(&self) -> Vec<&'static str> {
+        self.interfaces.iter().map(|i| i.name).collect()
+    }
+
+    /// Select the interface called `name`, or find the interface the module
+    /// implements if `name` is `AUTO_INTERFACE`.
+    pub fn select(
+        &self,
+        name: &str,
+        module: &ModuleSource,
+        config: &Config,
+    ) -> Result<&Interface<E>, Error> {
+        if name == AUTO_INTERFACE {
+            let exports = module_exports(module, config)?;
+            let interface = self
+                .interfaces
+                .iter()
+                .find(|i| i.is_implemented_by(&exports))
+                .ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "module {} does not implement any supported interface: it exports [{}], {}",
+                        module,
+                        exports.join(", "),
+                        self.describe()
+                    )
+                })?;
+            log::info!("Module {} implements {}", module, interface.name);
+            return Ok(interface);
+        }
+
+        self.interfaces
+            .iter()
+            .find(|i| i.name == name)
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "unknown interface '{}', expected one of: {}, {}",
+                    name,
+                    self.names().join(", "),
+                    AUTO_INTERFACE
+                )
+            })
+    }
+
+    /// Build an engine for the module using the interface selected by `name`.
+    pub fn build(&self, name: &str, module: &ModuleSource, config: &Config) -> Result<E, Error> {
+        let interface = self.select(name, module, config)?;
+        (interface.build)(module, config)
+    }
+
+    fn describe(&self) -> String {
+        self.interfaces
+            .iter()
+            .map(|i| format!("{} expects [{}]", i.name, i.exports.join(", ")))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// Names of the functions exported by a module.
+pub fn module_exports(module: &ModuleSource, config: &Config) -> Result<Vec<String>, Error> {
+    let bytes = module.read()?;
+    if bytes.starts_with(WASM_MAGIC) {
+        return wasm_exports(&bytes);
+    }
+
+    // Precompiled artifacts are loaded without compiling them again, and
+    // the text format has to be compiled to be parsed anyway.
+    let config = config.with_engine_features()?;
+    let engine = Engine::new(&config.wasi_config)?;
+    let local = matches!(module, ModuleSource::File(_));
+    let module = compile::load_module(&engine, &config, module.name(), &bytes, local)?;
+
+    Ok(module
+        .exports()
+        .filter(|e| e.ty().func().is_some())
+        .map(|e| e.name().to_string())
+        .collect())
+}
+
+/// Read the exported functions from the export section of a binary module,
+/// without compiling it.
+fn wasm_exports(bytes: &[u8]) -> Result<Vec<String>, Error> {
+    let mut exports = Vec::new();
+    for payload in Parser::new(0).parse_all(bytes) {
+        if let Payload::ExportSection(section) = payload? {
+            for export in section {
+                let export = export?;
+                if export.kind == ExternalKind::Function {
+                    exports.push(export.field.to_string());
+                }
+            }
+        }
+    }
+
+    Ok(exports)
+}
@@ -0,0 +1,116 @@
+use super::KvStore;
+use anyhow::Error;
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// A key-value store kept in memory, shared by all the instances of the
+/// process and lost when it exits.
+#[derive(Default)]
+pub struct MemoryStore {
+    namespaces: Mutex<HashMap<String, BTreeMap<String, Entry>>>,
+}
+
+struct Entry {
+    value: Vec<u8>,
+    expires: Option<Instant>,
+}
+
+impl Entry {
+    /// Create an entry expiring after `ttl`. TTLs too long to represent
+    /// an instant never expire.
+    fn new(value: &[u8], ttl: Option<Duration>) -> Self {
+        Self {
+            value: value.to_vec(),
+            expires: ttl.and_then(|ttl| Instant::now().checked_add(ttl)),
+        }
+    }
+
+    fn is_live(&self, now: Instant) -> bool {
+        self.expires.map_or(true, |expires| expires > now)
+    }
+}
+
+impl MemoryStore {
+    /// Get the live value of `key`, removing it if it has expired.
+    fn live<'a>(
+        keys: &'a mut BTreeMap<String, Entry>,
+        key: &str,
+        now: Instant,
+    ) -> Option<&'a [u8]> {
+        if keys.get(key).map_or(false, |entry| !entry.is_live(now)) {
+            keys.remove(key);
+        }
+        keys.get(key).map(|entry| entry.value.as_slice())
+    }
+}
+
+impl KvStore for MemoryStore {
+    fn get(&self, namespace: &str, key: &str) -> Result<Option<Vec<u8>>, Error> {
+        let mut namespaces = self.namespaces.lock().unwrap();
+        Ok(namespaces
+            .get_mut(namespace)
+            .and_then(|keys| Self::live(keys, key, Instant::now()))
+            .map(|value| value.to_vec()))
+    }
+
+    fn set(
+        &self,
+        namespace: &str,
+        key: &str,
+        value: &[u8],
+        ttl: Option<Duration>,
+    ) -> Result<(), Error> {
+        let mut namespaces = self.namespaces.lock().unwrap();
+        namespaces
+            .entry(namespace.to_string())
+            .or_default()
+            .insert(key.to_string(), Entry::new(value, ttl));
+        Ok(())
+    }
+
+    fn delete(&self, namespace: &str, key: &str) -> Result<bool, Error> {
+        let mut namespaces = self.namespaces.lock().unwrap();
+        let entry = namespaces
+            .get_mut(namespace)
+            .and_then(|keys| keys.remove(key));
+        Ok(entry.map_or(false, |entry| entry.is_live(Instant::now())))
+    }
+
+    fn list_keys(&self, namespace: &str, prefix: &str) -> Result<Vec<String>, Error> {
+        let now = Instant::now();
+        let mut namespaces = self.namespaces.lock().unwrap();
+        let keys = match namespaces.get_mut(namespace) {
+            Some(keys) => keys,
+            None => return Ok(Vec::new()),
+        };
+
+        keys.retain(|_, entry| entry.is_live(now));
+        Ok(keys
+            .range(prefix.to_string()..)
+            .map(|(key, _)| key)
+            .take_while(|key| key.starts_with(prefix))
+            .cloned()
+            .collect())
+    }
+
+    fn compare_and_swap(
+        &self,
+        namespace: &str,
+        key: &str,
+        expected: Option<&[u8]>,
+        value: &[u8],
+        ttl: Option<Duration>,
+    ) -> Result<bool, Error> {
+        let mut namespaces = self.namespaces.lock().unwrap();
+        let keys = namespaces.entry(namespace.to_string()).or_default();
+        if Self::live(keys, key, Instant::now()) != expected {
+            return Ok(false);
+        }
+
+        keys.insert(key.to_string(), Entry::new(value, ttl));
+        Ok(true)
+    }
+}
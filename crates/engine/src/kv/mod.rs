@@ -0,0 +1,189 @@
+//! Key-value store made available to guests through `glass_kv_v01`.
+
+mod memory;
+#[cfg(feature = "redis")]
+mod redis;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+
+#[cfg(feature = "redis")]
+pub use self::redis::RedisStore;
+#[cfg(feature = "sqlite")]
+pub use self::sqlite::SqliteStore;
+pub use memory::MemoryStore;
+
+use crate::{capability::HostCapability, Config, Context};
+use anyhow::Error;
+use glass_kv_v01::{GlassKvV01, KvError};
+use std::{path::PathBuf, str::FromStr, sync::Arc, time::Duration};
+use wasmtime::Linker;
+
+witx_bindgen_wasmtime::import!("crates/engine/glass_kv_v01.witx");
+
+/// A backend of the key-value store.
+///
+/// Keys are grouped in namespaces, and operations on one namespace never
+/// see the keys of another.
+pub trait KvStore: Send + Sync {
+    /// Get the value of `key`, if it is set and has not expired.
+    fn get(&self, namespace: &str, key: &str) -> Result<Option<Vec<u8>>, Error>;
+
+    /// Set the value of `key`, expiring after `ttl` if set.
+    fn set(
+        &self,
+        namespace: &str,
+        key: &str,
+        value: &[u8],
+        ttl: Option<Duration>,
+    ) -> Result<(), Error>;
+
+    /// Delete `key`, returning whether it was set.
+    fn delete(&self, namespace: &str, key: &str) -> Result<bool, Error>;
+
+    /// List the keys starting with `prefix`, in order.
+    fn list_keys(&self, namespace: &str, prefix: &str) -> Result<Vec<String>, Error>;
+
+    /// Set the value of `key` if its current value is `expected`, returning
+    /// whether it was set. `None` expects the key not to be set.
+    fn compare_and_swap(
+        &self,
+        namespace: &str,
+        key: &str,
+        expected: Option<&[u8]>,
+        value: &[u8],
+        ttl: Option<Duration>,
+    ) -> Result<bool, Error>;
+}
+
+/// Which backend the key-value store uses.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum KvBackend {
+    /// Values are kept in memory, and lost when the process exits.
+    Memory,
+    /// Values are kept in a local SQLite database file.
+    Sqlite(PathBuf),
+    /// Values are kept in the Redis server at the URL.
+    Redis(String),
+}
+
+impl KvBackend {
+    /// Open the store.
+    pub fn open(&self) -> Result<Arc<dyn KvStore>, Error> {
+        match self {
+            KvBackend::Memory => Ok(Arc::new(MemoryStore::default())),
+            #[cfg(feature = "sqlite")]
+            KvBackend::Sqlite(path) => Ok(Arc::new(SqliteStore::open(path)?)),
+            #[cfg(not(feature = "sqlite"))]
+            KvBackend::Sqlite(_) => anyhow::bail!("glass was built without SQLite support"),
+            #[cfg(feature = "redis")]
+            KvBackend::Redis(url) => Ok(Arc::new(RedisStore::open(url)?)),
+            #[cfg(not(feature = "redis"))]
+            KvBackend::Redis(_) => anyhow::bail!("glass was built without Redis support"),
+        }
+    }
+}
+
+impl FromStr for KvBackend {
+    type Err = Error;
+
+    /// Parse `memory`, `sqlite:<path>` or a `redis://` URL.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "memory" {
+            Ok(KvBackend::Memory)
+        } else if let Some(path) = s.strip_prefix("sqlite:") {
+            Ok(KvBackend::Sqlite(PathBuf::from(path)))
+        } else if s.starts_with("redis://") || s.starts_with("rediss://") {
+            Ok(KvBackend::Redis(s.to_string()))
+        } else {
+            anyhow::bail!("must be one of `memory`, `sqlite:<path>` or a `redis://` URL")
+        }
+    }
+}
+
+/// The key-value store host interface.
+pub struct Kv {
+    store: Arc<dyn KvStore>,
+    namespace: Option<String>,
+}
+
+impl Kv {
+    /// Make `store` available to guests, in the namespace configured on
+    /// the engine.
+    pub fn new(config: &Config, store: Arc<dyn KvStore>) -> Self {
+        Self {
+            store,
+            namespace: config.kv_namespace.clone(),
+        }
+    }
+}
+
+impl<T> HostCapability<T> for Kv {
+    fn add_to_linker(&self, linker: &mut Linker<Context<T>>) -> Result<(), Error> {
+        glass_kv_v01::add_to_linker(linker, |host| host.get_mut::<KvHost>().unwrap())?;
+        Ok(())
+    }
+
+    fn init_store(&self, ctx: &mut Context<T>) -> Result<(), Error> {
+        // Unless they share one explicitly, every module has its own namespace.
+        let namespace = match &self.namespace {
+            Some(namespace) => namespace.clone(),
+            None => ctx.module().to_string(),
+        };
+        ctx.insert(KvHost {
+            store: self.store.clone(),
+            namespace,
+        });
+        Ok(())
+    }
+}
+
+/// State of the key-value store in a store of an instance.
+struct KvHost {
+    store: Arc<dyn KvStore>,
+    namespace: String,
+}
+
+impl GlassKvV01 for KvHost {
+    fn get(&mut self, key: &str) -> Result<Option<Vec<u8>>, KvError> {
+        self.store.get(&self.namespace, key).map_err(unavailable)
+    }
+
+    fn set(&mut self, key: &str, value: &[u8], ttl: Option<u64>) -> Result<(), KvError> {
+        self.store
+            .set(&self.namespace, key, value, ttl.map(Duration::from_millis))
+            .map_err(unavailable)
+    }
+
+    fn delete(&mut self, key: &str) -> Result<bool, KvError> {
+        self.store.delete(&self.namespace, key).map_err(unavailable)
+    }
+
+    fn list_keys(&mut self, prefix: &str) -> Result<Vec<String>, KvError> {
+        self.store
+            .list_keys(&self.namespace, prefix)
+            .map_err(unavailable)
+    }
+
+    fn compare_and_swap(
+        &mut self,
+        key: &str,
+        expected: Option<&[u8]>,
+        value: &[u8],
+        ttl: Option<u64>,
+    ) -> Result<bool, KvError> {
+        self.store
+            .compare_and_swap(
+                &self.namespace,
+                key,
+                expected,
+                value,
+                ttl.map(Duration::from_millis),
+            )
+            .map_err(unavailable)
+    }
+}
+
+fn unavailable(e: Error) -> KvError {
+    log::warn!("Key-value store error: {:#}", e);
+    KvError::Unavailable
+}
@@ -0,0 +1,142 @@
+use super::KvStore;
+use anyhow::{Context, Error};
+use redis::{Commands, Connection, Script};
+use std::{convert::TryFrom, sync::Mutex, time::Duration};
+
+/// Set the key to the third argument if its value is the second argument,
+/// or if it is not set when the first argument is `0`.
+const COMPARE_AND_SWAP: &str = r#"
+local current = redis.call('GET', KEYS[1])
+if (ARGV[1] == '0' and current == false) or (ARGV[1] == '1' and current == ARGV[2]) then
+    if ARGV[4] == '' then
+        redis.call('SET', KEYS[1], ARGV[3])
+    else
+        redis.call('SET', KEYS[1], ARGV[3], 'PX', ARGV[4])
+    end
+    return 1
+end
+return 0
+"#;
+
+/// A key-value store kept in a Redis server.
+pub struct RedisStore {
+    conn: Mutex<Connection>,
+}
+
+impl RedisStore {
+    /// Connect to the server at `url`.
+    pub fn open(url: &str) -> Result<Self, Error> {
+        let conn = redis::Client::open(url)
+            .and_then(|client| client.get_connection())
+            .with_context(|| format!("failed to connect to Redis at '{}'", url))?;
+
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// Prefix of the Redis keys of a namespace. The length of the namespace
+    /// is part of it, so no namespace is a prefix of another.
+    fn prefix(namespace: &str) -> String {
+        format!("glass:{}:{}:", namespace.len(), namespace)
+    }
+
+    fn key(namespace: &str, key: &str) -> String {
+        format!("{}{}", Self::prefix(namespace), key)
+    }
+}
+
+impl KvStore for RedisStore {
+    fn get(&self, namespace: &str, key: &str) -> Result<Option<Vec<u8>>, Error> {
+        let mut conn = self.conn.lock().unwrap();
+        Ok(conn.get(Self::key(namespace, key))?)
+    }
+
+    fn set(
+        &self,
+        namespace: &str,
+        key: &str,
+        value: &[u8],
+        ttl: Option<Duration>,
+    ) -> Result<(), Error> {
+        let mut cmd = redis::cmd("SET");
+        cmd.arg(Self::key(namespace, key)).arg(value);
+        if let Some(px) = ttl.and_then(px) {
+            cmd.arg("PX").arg(px);
+        }
+
+        let mut conn = self.conn.lock().unwrap();
+        cmd.query::<()>(&mut *conn)?;
+        Ok(())
+    }
+
+    fn delete(&self, namespace: &str, key: &str) -> Result<bool, Error> {
+        let mut conn = self.conn.lock().unwrap();
+        let deleted: u64 = conn.del(Self::key(namespace, key))?;
+        Ok(deleted > 0)
+    }
+
+    fn list_keys(&self, namespace: &str, prefix: &str) -> Result<Vec<String>, Error> {
+        let namespace = Self::prefix(namespace);
+        let pattern = format!("{}{}*", escape(&namespace), escape(prefix));
+
+        let mut conn = self.conn.lock().unwrap();
+        let mut keys: Vec<String> = conn
+            .scan_match::<_, String>(pattern)?
+            .filter_map(|key| key.strip_prefix(&namespace).map(str::to_string))
+            .collect();
+        // SCAN can return a key more than once.
+        keys.sort();
+        keys.dedup();
+
+        Ok(keys)
+    }
+
+    fn compare_and_swap(
+        &self,
+        namespace: &str,
+        key: &str,
+        expected: Option<&[u8]>,
+        value: &[u8],
+        ttl: Option<Duration>,
+    ) -> Result<bool, Error> {
+        let mut conn = self.conn.lock().unwrap();
+        let swapped: u64 = Script::new(COMPARE_AND_SWAP)
+            .key(Self::key(namespace, key))
+            .arg(if expected.is_some() { "1" } else { "0" })
+            .arg(expected.unwrap_or_default())
+            .arg(value)
+            .arg(
+                ttl.and_then(px)
+                    .map(|px| px.to_string())
+                    .unwrap_or_default(),
+            )
+            .invoke(&mut *conn)?;
+
+        Ok(swapped == 1)
+    }
+}
+
+/// Longest TTL Redis accepts, as it rejects expirations past `i64::MAX`
+/// milliseconds since the epoch. Longer TTLs never expire.
+const MAX_PX: u64 = i64::MAX as u64 / 2;
+
+/// Redis rejects expirations of zero milliseconds, which are rounded up.
+fn px(ttl: Duration) -> Option<u64> {
+    u64::try_from(ttl.as_millis())
+        .ok()
+        .filter(|px| *px <= MAX_PX)
+        .map(|px| px.max(1))
+}
+
+/// Escape the characters that have a meaning in `SCAN` patterns.
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        if matches!(c, '*' | '?' | '[' | ']' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
@@ -0,0 +1,136 @@
+use super::KvStore;
+use anyhow::{Context, Error};
+use rusqlite::{params, Connection, OptionalExtension, TransactionBehavior};
+use std::{
+    convert::TryFrom,
+    path::Path,
+    sync::Mutex,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// A key-value store kept in a local SQLite database file, which persists
+/// across restarts and can be shared by several processes.
+pub struct SqliteStore {
+    conn: Mutex<Connection>,
+}
+
+impl SqliteStore {
+    /// Open the database at `path`, creating it if needed.
+    pub fn open(path: &Path) -> Result<Self, Error> {
+        let conn = Connection::open(path)
+            .with_context(|| format!("failed to open key-value database '{}'", path.display()))?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS glass_kv (
+                namespace  TEXT NOT NULL,
+                key        TEXT NOT NULL,
+                value      BLOB NOT NULL,
+                expires_at INTEGER,
+                PRIMARY KEY (namespace, key)
+            )",
+        )?;
+
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    fn get_live(conn: &Connection, namespace: &str, key: &str) -> Result<Option<Vec<u8>>, Error> {
+        Ok(conn
+            .query_row(
+                "SELECT value FROM glass_kv
+                 WHERE namespace = ?1 AND key = ?2 AND (expires_at IS NULL OR expires_at > ?3)",
+                params![namespace, key, now_ms()],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    fn insert(
+        conn: &Connection,
+        namespace: &str,
+        key: &str,
+        value: &[u8],
+        ttl: Option<Duration>,
+    ) -> Result<(), Error> {
+        let expires_at = ttl.map(|ttl| {
+            let ttl = i64::try_from(ttl.as_millis()).unwrap_or(i64::MAX);
+            now_ms().saturating_add(ttl)
+        });
+        conn.execute(
+            "INSERT OR REPLACE INTO glass_kv (namespace, key, value, expires_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![namespace, key, value, expires_at],
+        )?;
+        Ok(())
+    }
+}
+
+impl KvStore for SqliteStore {
+    fn get(&self, namespace: &str, key: &str) -> Result<Option<Vec<u8>>, Error> {
+        let conn = self.conn.lock().unwrap();
+        Self::get_live(&conn, namespace, key)
+    }
+
+    fn set(
+        &self,
+        namespace: &str,
+        key: &str,
+        value: &[u8],
+        ttl: Option<Duration>,
+    ) -> Result<(), Error> {
+        let conn = self.conn.lock().unwrap();
+        Self::insert(&conn, namespace, key, value, ttl)
+    }
+
+    fn delete(&self, namespace: &str, key: &str) -> Result<bool, Error> {
+        let conn = self.conn.lock().unwrap();
+        let existed = Self::get_live(&conn, namespace, key)?.is_some();
+        conn.execute(
+            "DELETE FROM glass_kv WHERE namespace = ?1 AND key = ?2",
+            params![namespace, key],
+        )?;
+        Ok(existed)
+    }
+
+    fn list_keys(&self, namespace: &str, prefix: &str) -> Result<Vec<String>, Error> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT key FROM glass_kv
+             WHERE namespace = ?1 AND substr(key, 1, length(?2)) = ?2
+                AND (expires_at IS NULL OR expires_at > ?3)
+             ORDER BY key",
+        )?;
+        let keys = stmt
+            .query_map(params![namespace, prefix, now_ms()], |row| row.get(0))?
+            .collect::<Result<_, _>>()?;
+        Ok(keys)
+    }
+
+    fn compare_and_swap(
+        &self,
+        namespace: &str,
+        key: &str,
+        expected: Option<&[u8]>,
+        value: &[u8],
+        ttl: Option<Duration>,
+    ) -> Result<bool, Error> {
+        // Other processes sharing the file cannot write between the
+        // comparison and the swap, since the transaction takes the write lock.
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        if Self::get_live(&tx, namespace, key)?.as_deref() != expected {
+            return Ok(false);
+        }
+
+        Self::insert(&tx, namespace, key, value, ttl)?;
+        tx.commit()?;
+        Ok(true)
+    }
+}
+
+fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as i64
+}
@@ -1,10 +1,49 @@
+pub mod source;
+
+pub mod capability;
+pub mod kv;
+pub mod publish;
+pub mod runtime_config;
+pub mod sqlite;
+
+mod cache;
+mod compile;
+mod interface;
+mod reload;
+mod stdio;
+mod watchdog;
+
+pub use cache::ModuleCache;
+pub use capability::HostCapability;
+pub use compile::{is_precompiled, precompile_module};
+pub use interface::{module_exports, Interface, InterfaceRegistry, AUTO_INTERFACE};
+pub use kv::{KvBackend, KvStore};
+pub use publish::{PublishBackend, Publisher};
+pub use reload::Reloadable;
+pub use runtime_config::{ConfigSource, RuntimeConfig};
+pub use source::ModuleSource;
+pub use sqlite::SqliteDatabase;
+pub use stdio::{StdioPolicy, DEFAULT_CAPTURE_BYTES};
+
+use crate::watchdog::{Deadline, Watchdog};
 use anyhow::Error;
-use std::{sync::Arc, time::Instant};
-use wasi_cap_std_sync::{ambient_authority, Dir, WasiCtxBuilder};
-use wasi_common::WasiCtx;
-use wasi_experimental_http_wasmtime::HttpCtx;
-use wasi_nn_onnx_wasmtime::WasiNnTractCtx;
-use wasmtime::{Engine, Instance, InstancePre, Linker, Module, Store};
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    fmt,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+use wasi_common::WasiFile;
+use wasmtime::{
+    Engine, Instance, InstanceAllocationStrategy, InstanceLimits, InstancePre, Linker,
+    ModuleLimits, PoolingAllocationStrategy, ResourceLimiter, Store, Trap, TrapCode,
+};
+use wasmtime_runtime::InstantiationError;
 
 /// Configuration for the engine.
 #[derive(Clone, Default)]
@@ -12,6 +51,46 @@ pub struct Config {
     pub vars: Vec<(String, String)>,
     pub preopen_dirs: Vec<(String, String)>,
     pub allowed_http_hosts: Option<Vec<String>>,
+    /// Fuel made available to every invocation. When set, guests that
+    /// exhaust their budget are stopped with `ExecutionError::OutOfFuel`.
+    pub max_fuel: Option<u64>,
+    /// Wall-clock deadline for a single invocation, and for running the start
+    /// function of the module when it is instantiated. Instances still running
+    /// when it elapses are interrupted with `ExecutionError::Timeout`.
+    pub timeout: Option<Duration>,
+    /// Limits on the resources a single instance can allocate.
+    pub resource_limits: ResourceLimits,
+    /// When set, instances are allocated from a pre-allocated pool
+    /// instead of on demand.
+    pub pooling: Option<PoolingConfig>,
+    /// Directory where compiled modules are cached across runs.
+    pub cache_dir: Option<PathBuf>,
+    /// Whether local module files can be artifacts built by
+    /// `precompile_module`. They contain native code that is not validated
+    /// when loading, so they are rejected unless allowed explicitly.
+    pub allow_precompiled: bool,
+    /// Whether guests get an empty standard input instead of the one of
+    /// the host process, unless the engine provides it.
+    pub empty_stdin: bool,
+    /// What happens to the output guests write to stdout, unless the
+    /// engine provides it.
+    pub stdout: StdioPolicy,
+    /// What happens to the output guests write to stderr.
+    pub stderr: StdioPolicy,
+    /// Backend of the key-value store guests can use, if any.
+    pub kv_store: Option<Arc<dyn KvStore>>,
+    /// Namespace of the key-value store. Unless set, every module has its
+    /// own namespace, named after the module.
+    pub kv_namespace: Option<String>,
+    /// SQLite databases guests can open.
+    pub sqlite_databases: Vec<SqliteDatabase>,
+    /// Deadline for a single SQLite statement. Statements still running
+    /// when it elapses are interrupted.
+    pub sqlite_timeout: Option<Duration>,
+    /// Configuration and secrets guests can read, if any.
+    pub runtime_config: Option<RuntimeConfig>,
+    /// Backend of the messages guests publish, if any.
+    pub publisher: Option<Arc<dyn Publisher>>,
     pub wasi_config: wasmtime::Config,
 }
 
@@ -29,30 +108,311 @@ impl Config {
             vars,
             preopen_dirs,
             allowed_http_hosts,
+            max_fuel: None,
+            timeout: None,
+            resource_limits: ResourceLimits::default(),
+            pooling: None,
+            cache_dir: None,
+            allow_precompiled: false,
+            empty_stdin: false,
+            stdout: StdioPolicy::default(),
+            stderr: StdioPolicy::default(),
+            kv_store: None,
+            kv_namespace: None,
+            sqlite_databases: Vec::new(),
+            sqlite_timeout: None,
+            runtime_config: None,
+            publisher: None,
             wasi_config,
         }
     }
+
+    /// Enable the Wasmtime features needed to enforce the execution
+    /// limits and allocation strategy configured for the engine.
+    fn with_engine_features(&self) -> Result<Self, Error> {
+        let mut config = self.clone();
+        if config.max_fuel.is_some() {
+            config.wasi_config.consume_fuel(true);
+        }
+        if config.timeout.is_some() {
+            config.wasi_config.interruptable(true);
+        }
+        if let Some(pooling) = &config.pooling {
+            config
+                .wasi_config
+                .allocation_strategy(pooling.allocation_strategy())?;
+        }
+
+        Ok(config)
+    }
+}
+
+/// Sizing of the instance pool used by the pooling allocation strategy.
+#[derive(Clone, Debug)]
+pub struct PoolingConfig {
+    /// Maximum number of instances that can be alive at the same time.
+    pub max_instances: u32,
+    /// Maximum number of 64 KiB pages of each instance's linear memory.
+    pub memory_pages: u64,
+    /// Maximum number of elements of each instance's table.
+    pub table_elements: u32,
+}
+
+impl Default for PoolingConfig {
+    fn default() -> Self {
+        Self {
+            max_instances: 1000,
+            memory_pages: 160,
+            table_elements: 10000,
+        }
+    }
+}
+
+impl PoolingConfig {
+    fn allocation_strategy(&self) -> InstanceAllocationStrategy {
+        InstanceAllocationStrategy::Pooling {
+            strategy: PoolingAllocationStrategy::NextAvailable,
+            module_limits: ModuleLimits {
+                memory_pages: self.memory_pages,
+                table_elements: self.table_elements,
+                ..Default::default()
+            },
+            instance_limits: InstanceLimits {
+                count: self.max_instances,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+/// Limits on the memories, tables and instances a guest can create.
+/// Unset limits fall back to the Wasmtime defaults.
+#[derive(Clone, Debug, Default)]
+pub struct ResourceLimits {
+    /// Maximum size in bytes of any linear memory.
+    pub max_memory_bytes: Option<usize>,
+    /// Maximum number of elements in any table.
+    pub max_table_elements: Option<u32>,
+    /// Maximum number of tables.
+    pub max_tables: Option<usize>,
+    /// Maximum number of instances, including the ones created
+    /// through module linking.
+    pub max_instances: Option<usize>,
+}
+
+/// Errors raised when a guest exceeds the execution limits set on `Config`.
+///
+/// These are attached as context to the underlying error, so engines can
+/// detect them using `anyhow::Error::downcast_ref`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExecutionError {
+    /// The guest consumed all of the fuel it was given.
+    OutOfFuel { budget: u64 },
+    /// The guest was interrupted after running past its deadline.
+    Timeout { timeout: Duration },
+    /// The guest tried to grow a linear memory past `ResourceLimits::max_memory_bytes`.
+    MemoryLimitExceeded { desired: usize, limit: usize },
+    /// The guest tried to grow a table past `ResourceLimits::max_table_elements`.
+    TableLimitExceeded { desired: u32, limit: u32 },
+    /// The guest tried to create more instances than `ResourceLimits::max_instances`.
+    InstanceLimitExceeded { limit: usize },
+    /// The guest tried to create more tables than `ResourceLimits::max_tables`.
+    TableCountExceeded { limit: usize },
+    /// All instances of the pooling allocator are in use.
+    PoolExhausted { max_instances: u32 },
+}
+
+impl fmt::Display for ExecutionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExecutionError::OutOfFuel { budget } => {
+                write!(f, "guest exhausted its fuel budget of {}", budget)
+            }
+            ExecutionError::Timeout { timeout } => {
+                write!(f, "guest did not complete within {:?}", timeout)
+            }
+            ExecutionError::MemoryLimitExceeded { desired, limit } => write!(
+                f,
+                "memory limit exceeded: guest requested {} bytes, limit is {}",
+                desired, limit
+            ),
+            ExecutionError::TableLimitExceeded { desired, limit } => write!(
+                f,
+                "table limit exceeded: guest requested {} elements, limit is {}",
+                desired, limit
+            ),
+            ExecutionError::InstanceLimitExceeded { limit } => {
+                write!(f, "instance limit exceeded: limit is {}", limit)
+            }
+            ExecutionError::TableCountExceeded { limit } => {
+                write!(f, "table count limit exceeded: limit is {}", limit)
+            }
+            ExecutionError::PoolExhausted { max_instances } => write!(
+                f,
+                "instance pool exhausted: all {} instances are in use",
+                max_instances
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ExecutionError {}
+
+/// Standard streams and environment of a single invocation, for engines
+/// that communicate with the guest through them.
+#[derive(Default)]
+pub struct Invocation {
+    /// Replaces the inherited standard input.
+    pub stdin: Option<Box<dyn WasiFile>>,
+    /// Replaces the inherited standard output.
+    pub stdout: Option<Box<dyn WasiFile>>,
+    /// Set in addition to the variables of the `Config`.
+    pub envs: Vec<(String, String)>,
 }
 
 /// Runtime data for the instances.
 /// The generic type can either be directly the `-Data` type generated
 /// by witx-bindgen, or if additional host imports need to be configured,
 /// it can also contain state related to those imports.
+///
+/// The state of the host capabilities is kept in extensions, keyed by type.
 #[derive(Default)]
 pub struct Context<T> {
-    pub wasi_ctx: Option<WasiCtx>,
-    pub nn_ctx: Option<WasiNnTractCtx>,
     pub runtime_data: Option<T>,
+    extensions: HashMap<TypeId, Box<dyn Any + Send>>,
+    module: String,
+    limiter: Limiter,
+}
+
+impl<T> Context<T> {
+    /// Get the extension of type `S`.
+    pub fn get<S: Any + Send>(&self) -> Option<&S> {
+        self.extensions
+            .get(&TypeId::of::<S>())
+            .and_then(|s| s.downcast_ref())
+    }
+
+    /// Get the extension of type `S` mutably.
+    pub fn get_mut<S: Any + Send>(&mut self) -> Option<&mut S> {
+        self.extensions
+            .get_mut(&TypeId::of::<S>())
+            .and_then(|s| s.downcast_mut())
+    }
+
+    /// Add an extension, replacing any previous one of the same type.
+    pub fn insert<S: Any + Send>(&mut self, state: S) {
+        self.extensions.insert(TypeId::of::<S>(), Box::new(state));
+    }
+
+    /// Remove the extension of type `S`, returning it.
+    pub fn remove<S: Any + Send>(&mut self) -> Option<S> {
+        self.extensions
+            .remove(&TypeId::of::<S>())
+            .and_then(|s| s.downcast().ok())
+            .map(|s| *s)
+    }
+
+    /// Name of the module the store instantiates.
+    pub fn module(&self) -> &str {
+        &self.module
+    }
+}
+
+// Wasmtime's own defaults for the number of instances, tables and memories.
+const DEFAULT_INSTANCE_LIMIT: usize = 10000;
+const DEFAULT_TABLE_LIMIT: usize = 10000;
+const DEFAULT_MEMORY_LIMIT: usize = 10000;
+
+/// Store limiter enforcing `ResourceLimits`, which remembers the
+/// first limit a guest ran into so it can be reported to the engine.
+#[derive(Default)]
+struct Limiter {
+    limits: ResourceLimits,
+    exceeded: Option<ExecutionError>,
+    /// Set while instantiating, when tables growing from zero elements are
+    /// being created rather than grown by the guest.
+    instantiating: bool,
+    /// Instances created in the store, counted as Wasmtime asks for their
+    /// limit once for each of them.
+    instances: AtomicUsize,
+    /// Tables created in the store.
+    tables: usize,
+}
+
+impl Limiter {
+    /// The first limit the guest ran into, if any.
+    fn exceeded(&self) -> Option<ExecutionError> {
+        let limit = self.instance_limit();
+        if self.instances.load(Ordering::SeqCst) > limit {
+            return Some(ExecutionError::InstanceLimitExceeded { limit });
+        }
+
+        self.exceeded.clone()
+    }
+
+    fn instance_limit(&self) -> usize {
+        self.limits.max_instances.unwrap_or(DEFAULT_INSTANCE_LIMIT)
+    }
+}
+
+impl ResourceLimiter for Limiter {
+    fn memory_growing(&mut self, _current: usize, desired: usize, _maximum: Option<usize>) -> bool {
+        match self.limits.max_memory_bytes {
+            Some(limit) if desired > limit => {
+                self.exceeded
+                    .get_or_insert(ExecutionError::MemoryLimitExceeded { desired, limit });
+                false
+            }
+            _ => true,
+        }
+    }
+
+    fn table_growing(&mut self, current: u32, desired: u32, _maximum: Option<u32>) -> bool {
+        if self.instantiating && current == 0 {
+            self.tables += 1;
+            let limit = self.limits.max_tables.unwrap_or(DEFAULT_TABLE_LIMIT);
+            if self.tables > limit {
+                self.exceeded
+                    .get_or_insert(ExecutionError::TableCountExceeded { limit });
+                return false;
+            }
+        }
+
+        match self.limits.max_table_elements {
+            Some(limit) if desired > limit => {
+                self.exceeded
+                    .get_or_insert(ExecutionError::TableLimitExceeded { desired, limit });
+                false
+            }
+            _ => true,
+        }
+    }
+
+    fn instances(&self) -> usize {
+        self.instances.fetch_add(1, Ordering::SeqCst);
+        self.instance_limit()
+    }
+
+    fn tables(&self) -> usize {
+        // Tables are counted by `table_growing` as they are created instead.
+        usize::MAX
+    }
+
+    fn memories(&self) -> usize {
+        DEFAULT_MEMORY_LIMIT
+    }
 }
 
 /// A builder that helps configure and build `WasiExecutionContext` instances.
 ///
-/// Additional host imports can be defined using the linker and store fields.
+/// Additional host imports can be defined using `with_capability`, or
+/// directly using the linker and store fields.
 pub struct WasiExecutionContextBuilder<T: Default> {
     pub config: Config,
     pub engine: Engine,
     pub store: Store<Context<T>>,
     pub linker: Linker<Context<T>>,
+    capabilities: Vec<Arc<dyn HostCapability<T>>>,
 }
 
 impl<T: Default> WasiExecutionContextBuilder<T> {
@@ -70,7 +430,7 @@ impl<T: Default> WasiExecutionContextBuilder<T> {
 
     /// Create a new `WasiExecutionContextBuilder`.
     pub fn new(config: &Config) -> Result<Self, Error> {
-        let config = config.clone();
+        let config = config.with_engine_features()?;
         let engine = Engine::new(&config.wasi_config)?;
         let linker: Linker<Context<T>> = Linker::new(&engine);
         let store: Store<Context<T>> = Store::new(&engine, Context::default());
@@ -80,39 +440,88 @@ impl<T: Default> WasiExecutionContextBuilder<T> {
             engine,
             store,
             linker,
+            capabilities: Vec::new(),
         })
     }
 
+    /// Make a host capability available to the module.
+    pub fn with_capability(
+        &mut self,
+        capability: impl HostCapability<T> + 'static,
+    ) -> Result<&mut Self, Error> {
+        capability.add_to_linker(&mut self.linker)?;
+        self.capabilities.push(Arc::new(capability));
+        Ok(self)
+    }
+
     /// Configure support for the core WASI API.
     pub fn add_wasi(&mut self) -> Result<&mut Self, Error> {
-        wasmtime_wasi::add_to_linker(&mut self.linker, |host| host.wasi_ctx.as_mut().unwrap())?;
-        Ok(self)
+        let wasi = capability::Wasi::new(&self.config);
+        self.with_capability(wasi)
     }
 
     /// Configure support for experimental outbound HTTP support.
     pub fn add_experimental_http(&mut self) -> Result<&mut Self, Error> {
-        HttpCtx::new(self.config.allowed_http_hosts.clone(), None)?
-            .add_to_linker(&mut self.linker)?;
-        Ok(self)
+        let http = capability::ExperimentalHttp::new(&self.config);
+        self.with_capability(http)
     }
 
     /// Configure WASI NN for the current context using the self-contained
     /// ONNX implementation for WASI NN.
     pub fn add_nn(&mut self) -> Result<&mut Self, Error> {
-        wasi_nn_onnx_wasmtime::add_to_linker(&mut self.linker, |host| {
-            host.nn_ctx.as_mut().unwrap()
-        })?;
-        Ok(self)
+        self.with_capability(capability::Nn)
+    }
+
+    /// Configure the key-value store, using `store` as its backend.
+    pub fn add_kv(&mut self, store: Arc<dyn KvStore>) -> Result<&mut Self, Error> {
+        let kv = kv::Kv::new(&self.config, store);
+        self.with_capability(kv)
+    }
+
+    /// Configure access to the SQLite databases configured on the engine.
+    #[cfg(feature = "sqlite")]
+    pub fn add_sqlite(&mut self) -> Result<&mut Self, Error> {
+        let sqlite = sqlite::Sqlite::new(&self.config);
+        self.with_capability(sqlite)
+    }
+
+    #[cfg(not(feature = "sqlite"))]
+    pub fn add_sqlite(&mut self) -> Result<&mut Self, Error> {
+        anyhow::bail!("glass-engine was built without the `sqlite` feature")
+    }
+
+    /// Configure the runtime configuration guests can read.
+    pub fn add_runtime_config(&mut self, config: RuntimeConfig) -> Result<&mut Self, Error> {
+        self.with_capability(config)
+    }
+
+    /// Configure message publishing, using `publisher` as its backend.
+    pub fn add_publish(&mut self, publisher: Arc<dyn Publisher>) -> Result<&mut Self, Error> {
+        self.with_capability(publish::Publish::new(publisher))
     }
 
     /// Configure all available host imports.
     ///
     /// Currently, this includes core WASI, experimental HTTP
-    /// support, and the ONNX implementation of WASI NN.
+    /// support, the ONNX implementation of WASI NN, and the key-value
+    /// store, SQLite databases, runtime configuration and message
+    /// publishing if they are configured.
     pub fn add_all(&mut self) -> Result<&mut Self, Error> {
         self.add_wasi()?;
         self.add_experimental_http()?;
         self.add_nn()?;
+        if let Some(store) = self.config.kv_store.clone() {
+            self.add_kv(store)?;
+        }
+        if !self.config.sqlite_databases.is_empty() {
+            self.add_sqlite()?;
+        }
+        if let Some(config) = self.config.runtime_config.clone() {
+            self.add_runtime_config(config)?;
+        }
+        if let Some(publisher) = self.config.publisher.clone() {
+            self.add_publish(publisher)?;
+        }
 
         Ok(self)
     }
@@ -120,11 +529,48 @@ impl<T: Default> WasiExecutionContextBuilder<T> {
     /// Create a `WasiExecutionContext` using the configured store
     /// and linker, and pre-instantiate the entrypoint WebAssembly module.
     pub fn build(&mut self, entrypoint_path: &str) -> Result<WasiExecutionContext<T>, Error> {
+        let bytes = anyhow::Context::with_context(std::fs::read(entrypoint_path), || {
+            format!("failed to read module '{}'", entrypoint_path)
+        })?;
+
+        self.build_module(entrypoint_path, &bytes, true)
+    }
+
+    /// Create a `WasiExecutionContext` for the entrypoint module loaded from `source`.
+    pub fn build_source(
+        &mut self,
+        source: &ModuleSource,
+    ) -> Result<WasiExecutionContext<T>, Error> {
+        match source {
+            ModuleSource::File(path) => self.build(path),
+            ModuleSource::Bytes { name, bytes } => self.build_from_bytes(name, bytes),
+        }
+    }
+
+    /// Create a `WasiExecutionContext` from the contents of a WebAssembly module.
+    /// `name` identifies the module in logs.
+    ///
+    /// Artifacts built by `precompile_module` can only be loaded from local
+    /// files, using `build`.
+    pub fn build_from_bytes(
+        &mut self,
+        name: &str,
+        bytes: &[u8],
+    ) -> Result<WasiExecutionContext<T>, Error> {
+        self.build_module(name, bytes, false)
+    }
+
+    fn build_module(
+        &mut self,
+        name: &str,
+        bytes: &[u8],
+        local: bool,
+    ) -> Result<WasiExecutionContext<T>, Error> {
         let start = Instant::now();
 
         let (config, engine) = (self.config.clone(), self.engine.clone());
-        let module = Module::from_file(&self.engine, &entrypoint_path)?;
-        let entrypoint_path = entrypoint_path.to_string();
+        let module = compile::load_module(&engine, &config, name, bytes, local)?;
+        let entrypoint_path = name.to_string();
         let pre = Arc::new(self.linker.instantiate_pre(&mut self.store, &module)?);
 
         log::info!(
@@ -137,6 +583,8 @@ impl<T: Default> WasiExecutionContextBuilder<T> {
             config,
             pre,
             engine,
+            capabilities: Arc::new(self.capabilities.clone()),
+            watchdog: config.timeout.map(|_| Arc::new(Watchdog::new())),
         })
     }
 }
@@ -150,51 +598,127 @@ pub struct WasiExecutionContext<T: Default> {
     config: Config,
     pre: Arc<InstancePre<Context<T>>>,
     engine: Engine,
+    capabilities: Arc<Vec<Arc<dyn HostCapability<T>>>>,
+    /// Enforces `Config::timeout` for all instances of the context.
+    watchdog: Option<Arc<Watchdog>>,
 }
 
 impl<T: Default> WasiExecutionContext<T> {
-    fn create_store(&self, data: Option<T>) -> Result<Store<Context<T>>, Error> {
-        let mut store: Store<Context<T>> = Store::new(&self.engine, Context::default());
-        let mut builder = WasiCtxBuilder::new()
-            .inherit_stdin()
-            .inherit_stdout()
-            .inherit_stderr()
-            .envs(&self.config.vars)?;
-
-        let preopen_dirs = Self::compute_preopen_dirs(self.config.preopen_dirs.clone())?;
+    /// Get the configuration the context was built with.
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
 
-        for (name, dir) in preopen_dirs.into_iter() {
-            builder = builder.preopened_dir(dir, name)?;
+    fn create_store(
+        &self,
+        data: Option<T>,
+        invocation: Invocation,
+    ) -> Result<Store<Context<T>>, Error> {
+        let mut ctx = Context {
+            runtime_data: data,
+            module: self.entrypoint_path.clone(),
+            ..Context::default()
+        };
+        // Consumed by the capability handling the standard streams.
+        ctx.insert(invocation);
+        ctx.limiter.limits = self.config.resource_limits.clone();
+        for capability in self.capabilities.iter() {
+            capability.init_store(&mut ctx)?;
         }
 
-        store.data_mut().wasi_ctx = Some(builder.build());
-        store.data_mut().nn_ctx = Some(WasiNnTractCtx::default());
-        store.data_mut().runtime_data = data;
+        let mut store: Store<Context<T>> = Store::new(&self.engine, ctx);
+        store.limiter(|ctx| &mut ctx.limiter);
 
-        Ok(store)
-    }
-
-    fn compute_preopen_dirs(map_dirs: Vec<(String, String)>) -> Result<Vec<(String, Dir)>, Error> {
-        let mut preopen_dirs = Vec::new();
-        for (guest, host) in map_dirs.iter() {
-            preopen_dirs.push((
-                guest.clone(),
-                anyhow::Context::with_context(
-                    Dir::open_ambient_dir(host, ambient_authority()),
-                    || format!("failed to open directory '{}'", host),
-                )?,
-            ));
+        if let Some(fuel) = self.config.max_fuel {
+            store.add_fuel(fuel)?;
         }
 
-        Ok(preopen_dirs)
+        Ok(store)
     }
 
     /// Prepare the execution by finishing the instantiation proces for the module
     /// using real runtime data, then return the store and instance to be used by the engine.
     pub fn prepare_exec(&self, data: Option<T>) -> Result<(Store<Context<T>>, Instance), Error> {
-        let mut store = self.create_store(data)?;
-        let instance = self.pre.instantiate(&mut store)?;
+        self.prepare_exec_with(data, Invocation::default())
+    }
+
+    /// Like `prepare_exec`, with the standard streams and environment
+    /// variables of the instance adjusted by `invocation`.
+    pub fn prepare_exec_with(
+        &self,
+        data: Option<T>,
+        invocation: Invocation,
+    ) -> Result<(Store<Context<T>>, Instance), Error> {
+        let start = Instant::now();
+        let mut store = self.create_store(data, invocation)?;
+        // The start function of the module runs during instantiation.
+        let deadline = self.start_deadline(&store)?;
+        store.data_mut().limiter.instantiating = true;
+        let instance = self.pre.instantiate(&mut store);
+        store.data_mut().limiter.instantiating = false;
+        let instance = instance.map_err(|e| self.classify_instantiation_error(&store, e))?;
+        drop(deadline);
 
+        log::info!("Instantiated module in: {:?}", start.elapsed());
         Ok((store, instance))
     }
+
+    /// Call into an instance created by `prepare_exec`, reporting guests
+    /// that ran out of their execution limits as `ExecutionError`.
+    pub fn call<R>(
+        &self,
+        store: &mut Store<Context<T>>,
+        f: impl FnOnce(&mut Store<Context<T>>) -> Result<R, Error>,
+    ) -> Result<R, Error> {
+        let _deadline = self.start_deadline(store)?;
+
+        f(store).map_err(|e| self.classify_error(store, e))
+    }
+
+    /// Interrupt the instance of `store` once `Config::timeout` elapses,
+    /// unless the returned deadline is dropped first.
+    fn start_deadline(&self, store: &Store<Context<T>>) -> Result<Option<Deadline>, Error> {
+        match (&self.watchdog, self.config.timeout) {
+            (Some(watchdog), Some(timeout)) => {
+                Ok(Some(watchdog.start(store.interrupt_handle()?, timeout)))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn classify_instantiation_error(&self, store: &Store<Context<T>>, err: Error) -> Error {
+        if let Some(InstantiationError::Limit(max_instances)) = err.downcast_ref() {
+            let max_instances = *max_instances;
+            log::warn!(
+                "Instance pool exhausted ({} instances in use)",
+                max_instances
+            );
+            return err.context(ExecutionError::PoolExhausted { max_instances });
+        }
+
+        self.classify_error(store, err)
+    }
+
+    fn classify_error(&self, store: &Store<Context<T>>, err: Error) -> Error {
+        if let Some(exceeded) = store.data().limiter.exceeded() {
+            return err.context(exceeded);
+        }
+        if let Some(timeout) = self.config.timeout {
+            let interrupted = err
+                .downcast_ref::<Trap>()
+                .and_then(|t| t.trap_code())
+                .map_or(false, |code| code == TrapCode::Interrupt);
+            if interrupted {
+                return err.context(ExecutionError::Timeout { timeout });
+            }
+        }
+
+        if let Some(budget) = self.config.max_fuel {
+            if store.fuel_consumed().map_or(false, |used| used >= budget) {
+                return err.context(ExecutionError::OutOfFuel { budget });
+            }
+        }
+
+        err
+    }
 }
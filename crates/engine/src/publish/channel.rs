@@ -0,0 +1,37 @@
+use super::{Message, Publisher};
+use anyhow::Error;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+
+/// Number of messages kept for subscribers that fall behind. Older
+/// messages are dropped for them.
+const CAPACITY: usize = 1024;
+
+/// Publishes messages to the subscribers in the same process, such as
+/// other triggers.
+#[derive(Clone)]
+pub struct ChannelPublisher {
+    sender: broadcast::Sender<Arc<Message>>,
+}
+
+impl Default for ChannelPublisher {
+    fn default() -> Self {
+        let (sender, _) = broadcast::channel(CAPACITY);
+        Self { sender }
+    }
+}
+
+impl Publisher for ChannelPublisher {
+    fn publish(&self, message: &Message) -> Result<(), Error> {
+        // Sending only fails when nobody is subscribed, and then there is
+        // nobody to deliver the message to either.
+        if self.sender.send(Arc::new(message.clone())).is_err() {
+            log::debug!("No subscriber for message to '{}'", message.topic);
+        }
+        Ok(())
+    }
+
+    fn subscribe(&self) -> Option<broadcast::Receiver<Arc<Message>>> {
+        Some(self.sender.subscribe())
+    }
+}
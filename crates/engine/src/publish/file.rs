@@ -0,0 +1,55 @@
+use super::{Message, Publisher};
+use anyhow::{Context, Error};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs::{File, OpenOptions},
+    io::Write,
+    path::Path,
+    sync::Mutex,
+};
+
+/// Publishes messages to a local file, appending one JSON object per line.
+pub struct FilePublisher {
+    file: Mutex<File>,
+}
+
+/// A line of the file.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileRecord {
+    pub topic: String,
+    /// The payload, encoded in base64.
+    pub payload: String,
+    pub attributes: BTreeMap<String, String>,
+}
+
+impl FilePublisher {
+    /// Open the file at `path`, creating it if needed.
+    pub fn open(path: &Path) -> Result<Self, Error> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("failed to open '{}'", path.display()))?;
+
+        Ok(Self {
+            file: Mutex::new(file),
+        })
+    }
+}
+
+impl Publisher for FilePublisher {
+    fn publish(&self, message: &Message) -> Result<(), Error> {
+        let record = FileRecord {
+            topic: message.topic.clone(),
+            payload: base64::encode(&message.payload),
+            attributes: message.attributes.iter().cloned().collect(),
+        };
+        let mut line = serde_json::to_vec(&record)?;
+        line.push(b'\n');
+
+        // A single write per line, so lines of concurrent writers are not interleaved.
+        self.file.lock().unwrap().write_all(&line)?;
+        Ok(())
+    }
+}
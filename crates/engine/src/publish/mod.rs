@@ -0,0 +1,154 @@
+//! Outbound messages published by guests through `glass_publish_v01`.
+
+mod channel;
+mod file;
+#[cfg(feature = "mqtt")]
+mod mqtt;
+#[cfg(feature = "nats")]
+mod nats;
+
+#[cfg(feature = "nats")]
+pub use self::nats::NatsPublisher;
+pub use channel::ChannelPublisher;
+pub use file::{FilePublisher, FileRecord};
+#[cfg(feature = "mqtt")]
+pub use mqtt::MqttPublisher;
+
+use crate::{capability::HostCapability, Context};
+use anyhow::Error;
+use glass_publish_v01::{GlassPublishV01, PublishError};
+use std::{path::PathBuf, str::FromStr, sync::Arc};
+use tokio::sync::broadcast;
+use wasmtime::Linker;
+
+witx_bindgen_wasmtime::import!("crates/engine/glass_publish_v01.witx");
+
+/// A message published by a guest.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Message {
+    pub topic: String,
+    pub payload: Vec<u8>,
+    pub attributes: Vec<(String, String)>,
+}
+
+/// A backend messages are published to.
+pub trait Publisher: Send + Sync {
+    fn publish(&self, message: &Message) -> Result<(), Error>;
+
+    /// Receive the messages published from now on, if the backend delivers
+    /// them in the same process. Triggers use it to run guests for the
+    /// messages other guests publish.
+    fn subscribe(&self) -> Option<broadcast::Receiver<Arc<Message>>> {
+        None
+    }
+}
+
+/// Which backend messages are published to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PublishBackend {
+    /// Messages are appended to a file, one JSON object per line.
+    File(PathBuf),
+    /// Messages are delivered to the triggers of the same process that
+    /// subscribe through `Publisher::subscribe`.
+    Channel,
+    /// Messages are published to the NATS server at the URL.
+    Nats(String),
+    /// Messages are published to the MQTT broker at the URL.
+    Mqtt(String),
+}
+
+impl PublishBackend {
+    /// Open the backend.
+    pub fn open(&self) -> Result<Arc<dyn Publisher>, Error> {
+        match self {
+            PublishBackend::File(path) => Ok(Arc::new(FilePublisher::open(path)?)),
+            PublishBackend::Channel => Ok(Arc::new(ChannelPublisher::default())),
+            #[cfg(feature = "nats")]
+            PublishBackend::Nats(url) => Ok(Arc::new(NatsPublisher::connect(url)?)),
+            #[cfg(not(feature = "nats"))]
+            PublishBackend::Nats(_) => anyhow::bail!("glass was built without NATS support"),
+            #[cfg(feature = "mqtt")]
+            PublishBackend::Mqtt(url) => Ok(Arc::new(MqttPublisher::connect(url)?)),
+            #[cfg(not(feature = "mqtt"))]
+            PublishBackend::Mqtt(_) => anyhow::bail!("glass was built without MQTT support"),
+        }
+    }
+}
+
+impl FromStr for PublishBackend {
+    type Err = Error;
+
+    /// Parse `file:<path>`, `channel`, or a `nats://` or `mqtt://` URL.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "channel" {
+            Ok(PublishBackend::Channel)
+        } else if let Some(path) = s.strip_prefix("file:") {
+            Ok(PublishBackend::File(PathBuf::from(path)))
+        } else if s.starts_with("nats://") || s.starts_with("tls://") {
+            Ok(PublishBackend::Nats(s.to_string()))
+        } else if s.starts_with("mqtt://") {
+            Ok(PublishBackend::Mqtt(s.to_string()))
+        } else {
+            anyhow::bail!(
+                "must be one of `file:<path>`, `channel`, or a `nats://` or `mqtt://` URL"
+            )
+        }
+    }
+}
+
+/// Whether guests can publish to `topic`.
+pub fn is_valid_topic(topic: &str) -> bool {
+    !topic.is_empty() && !topic.chars().any(|c| c.is_whitespace() || c.is_control())
+}
+
+/// The publish host interface.
+pub struct Publish {
+    publisher: Arc<dyn Publisher>,
+}
+
+impl Publish {
+    /// Let guests publish messages to `publisher`.
+    pub fn new(publisher: Arc<dyn Publisher>) -> Self {
+        Self { publisher }
+    }
+}
+
+impl<T> HostCapability<T> for Publish {
+    fn add_to_linker(&self, linker: &mut Linker<Context<T>>) -> Result<(), Error> {
+        glass_publish_v01::add_to_linker(linker, |host| host.get_mut::<PublishHost>().unwrap())?;
+        Ok(())
+    }
+
+    fn init_store(&self, ctx: &mut Context<T>) -> Result<(), Error> {
+        ctx.insert(PublishHost(self.publisher.clone()));
+        Ok(())
+    }
+}
+
+struct PublishHost(Arc<dyn Publisher>);
+
+impl GlassPublishV01 for PublishHost {
+    fn publish(
+        &mut self,
+        topic: &str,
+        payload: &[u8],
+        attributes: Vec<(&str, &str)>,
+    ) -> Result<(), PublishError> {
+        if !is_valid_topic(topic) {
+            return Err(PublishError::InvalidTopic);
+        }
+
+        let message = Message {
+            topic: topic.to_string(),
+            payload: payload.to_vec(),
+            attributes: attributes
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        };
+        self.0.publish(&message).map_err(|e| {
+            log::warn!("Cannot publish message to '{}': {:#}", topic, e);
+            PublishError::Unavailable
+        })
+    }
+}
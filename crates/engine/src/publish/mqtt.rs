@@ -0,0 +1,92 @@
+use super::{Message, Publisher};
+use anyhow::{Context, Error};
+use rumqttc::{Client, Connection, Event, MqttOptions, Packet, QoS};
+use std::{sync::mpsc, thread, time::Duration};
+
+const DEFAULT_PORT: u16 = 1883;
+const KEEP_ALIVE: Duration = Duration::from_secs(30);
+
+/// Publishes messages to an MQTT broker with QoS 1.
+///
+/// Publishing only queues the message for the background thread driving
+/// the connection, without waiting for the broker to acknowledge it, so
+/// messages still queued when the process exits are lost. MQTT 3.1.1 has
+/// no message metadata, so attributes are not sent.
+pub struct MqttPublisher {
+    client: Client,
+}
+
+impl MqttPublisher {
+    /// Connect to the broker at a `mqtt://host[:port]` URL.
+    pub fn connect(url: &str) -> Result<Self, Error> {
+        let address = url
+            .strip_prefix("mqtt://")
+            .context("the URL of the MQTT broker must start with `mqtt://`")?
+            .trim_end_matches('/');
+        let (host, port) = match address.rsplit_once(':') {
+            Some((host, port)) => (host, port.parse().context("invalid MQTT port")?),
+            None => (address, DEFAULT_PORT),
+        };
+
+        let client_id = format!("glass-{}", std::process::id());
+        let mut options = MqttOptions::new(client_id, host, port);
+        options.set_keep_alive(KEEP_ALIVE);
+        let (client, connection) = Client::new(options, 100);
+
+        // The connection must be polled for messages to be sent. Wait for the
+        // broker to accept it, so unreachable brokers fail at startup.
+        let (connected, accepted) = mpsc::channel();
+        thread::spawn(move || poll(connection, connected));
+        accepted
+            .recv_timeout(KEEP_ALIVE)
+            .map_err(|_| anyhow::anyhow!("timed out connecting to MQTT at '{}'", url))?
+            .with_context(|| format!("failed to connect to MQTT at '{}'", url))?;
+
+        Ok(Self { client })
+    }
+}
+
+/// Drive the connection, reporting the outcome of the first attempt.
+fn poll(mut connection: Connection, connected: mpsc::Sender<Result<(), Error>>) {
+    let mut connected = Some(connected);
+    for event in connection.iter() {
+        match event {
+            Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                if let Some(connected) = connected.take() {
+                    let _ = connected.send(Ok(()));
+                }
+            }
+            Ok(_) => {}
+            Err(e) => match connected.take() {
+                Some(connected) => {
+                    let _ = connected.send(Err(e.into()));
+                    return;
+                }
+                // The client reconnects on the next iteration.
+                None => {
+                    log::warn!("MQTT connection error: {}", e);
+                    thread::sleep(Duration::from_secs(1));
+                }
+            },
+        }
+    }
+}
+
+impl Publisher for MqttPublisher {
+    fn publish(&self, message: &Message) -> Result<(), Error> {
+        if !message.attributes.is_empty() {
+            log::debug!(
+                "Dropping the attributes of message to '{}', which MQTT cannot send",
+                message.topic
+            );
+        }
+
+        self.client.clone().publish(
+            message.topic.as_str(),
+            QoS::AtLeastOnce,
+            false,
+            message.payload.clone(),
+        )?;
+        Ok(())
+    }
+}
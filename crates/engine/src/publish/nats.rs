@@ -0,0 +1,39 @@
+use super::{Message, Publisher};
+use anyhow::{Context, Error};
+use nats::{header::HeaderMap, Connection};
+
+/// Publishes messages to a NATS server, with the topic as the subject and
+/// the attributes as headers.
+pub struct NatsPublisher {
+    conn: Connection,
+}
+
+impl NatsPublisher {
+    /// Connect to the server at `url`.
+    pub fn connect(url: &str) -> Result<Self, Error> {
+        let conn = nats::connect(url)
+            .with_context(|| format!("failed to connect to NATS at '{}'", url))?;
+        Ok(Self { conn })
+    }
+}
+
+impl Publisher for NatsPublisher {
+    fn publish(&self, message: &Message) -> Result<(), Error> {
+        if message.attributes.is_empty() {
+            self.conn.publish(&message.topic, &message.payload)?;
+            return Ok(());
+        }
+
+        let mut headers = HeaderMap::new();
+        for (name, value) in &message.attributes {
+            headers.insert(name.as_str(), value.clone());
+        }
+        self.conn.publish_with_reply_or_headers(
+            &message.topic,
+            None,
+            Some(&headers),
+            &message.payload,
+        )?;
+        Ok(())
+    }
+}
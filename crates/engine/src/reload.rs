@@ -0,0 +1,104 @@
+use anyhow::Error;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+
+/// An engine that can be rebuilt and atomically replaced while serving.
+///
+/// Each invocation uses a clone of the current engine, so invocations that
+/// started before a reload complete on the previous version of the module.
+pub struct Reloadable<E> {
+    current: Arc<RwLock<E>>,
+    build: Arc<dyn Fn() -> Result<E, Error> + Send + Sync>,
+}
+
+impl<E> Clone for Reloadable<E> {
+    fn clone(&self) -> Self {
+        Self {
+            current: self.current.clone(),
+            build: self.build.clone(),
+        }
+    }
+}
+
+impl<E: Clone + Send + Sync + 'static> Reloadable<E> {
+    /// Build the initial engine, keeping `build` around to rebuild it on reloads.
+    pub fn new(
+        build: impl Fn() -> Result<E, Error> + Send + Sync + 'static,
+    ) -> Result<Self, Error> {
+        let current = Arc::new(RwLock::new(build()?));
+
+        Ok(Self {
+            current,
+            build: Arc::new(build),
+        })
+    }
+
+    /// Get the current engine.
+    pub fn current(&self) -> E {
+        self.current.read().unwrap().clone()
+    }
+
+    /// Rebuild the engine and swap it in. If the build fails, the error is
+    /// logged and the previous engine keeps serving.
+    pub fn reload(&self) -> bool {
+        let start = Instant::now();
+        match (self.build)() {
+            Ok(engine) => {
+                *self.current.write().unwrap() = engine;
+                log::info!("Reloaded engine in: {:?}", start.elapsed());
+                true
+            }
+            Err(e) => {
+                log::error!("Cannot reload engine, keeping the previous one: {:#}", e);
+                false
+            }
+        }
+    }
+
+    /// Reload the engine in the background whenever the file at `path` changes,
+    /// checking for changes every `interval`. If `path` is a directory, adding,
+    /// removing or changing any file in it is a change too.
+    pub fn watch(&self, path: impl Into<PathBuf>, interval: Duration) {
+        let path = path.into();
+        let engine = self.clone();
+
+        thread::spawn(move || {
+            let mut last = modified(&path);
+            loop {
+                thread::sleep(interval);
+                let current = modified(&path);
+                if current.is_some() && current != last {
+                    log::info!("{} changed, reloading", path.display());
+                    last = current;
+                    engine.reload();
+                }
+            }
+        });
+    }
+}
+
+/// Modification times of `path` and, if it is a directory, of its entries.
+fn modified(path: &Path) -> Option<Vec<(PathBuf, SystemTime)>> {
+    let mtime = |path: &Path| fs::metadata(path).and_then(|m| m.modified()).ok();
+
+    let mut times = vec![(path.to_path_buf(), mtime(path)?)];
+    if path.is_dir() {
+        let mut entries: Vec<_> = fs::read_dir(path)
+            .ok()?
+            .filter_map(|entry| {
+                let entry = entry.ok()?.path();
+                // Entries can disappear while listing the directory.
+                Some((entry.clone(), mtime(&entry)?))
+            })
+            .collect();
+        entries.sort();
+        times.extend(entries);
+    }
+
+    Some(times)
+}
@@ -0,0 +1,267 @@
+//! Configuration and secrets guests read at runtime through `glass_config_v01`.
+//!
+//! Values are resolved from layered sources, and can be reloaded without
+//! rebuilding the engine. Instances see the values current when they start.
+
+use crate::{capability::HostCapability, Context, Reloadable};
+use anyhow::{Context as _, Error};
+use glass_config_v01::GlassConfigV01;
+use std::{
+    collections::HashMap,
+    fmt, fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+use wasmtime::Linker;
+
+witx_bindgen_wasmtime::import!("crates/engine/glass_config_v01.witx");
+
+/// Shown instead of secret values in logs.
+pub const REDACTED: &str = "[REDACTED]";
+
+/// A source of configuration values.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConfigSource {
+    /// Values given directly, e.g. on the command line.
+    Values(Vec<(String, String)>),
+    /// A TOML file. Nested tables are flattened, so `url` in the `[db]`
+    /// table is the key `db.url`.
+    File(PathBuf),
+    /// A directory where every file holds a secret, named after the file.
+    SecretsDir(PathBuf),
+    /// The environment variables of the host starting with `prefix`.
+    /// Keys are the rest of the name, in lower case.
+    Env { prefix: String },
+}
+
+/// A value of the configuration.
+#[derive(Clone, PartialEq, Eq)]
+pub struct ConfigValue {
+    pub value: String,
+    /// Whether the value is redacted when displayed.
+    pub secret: bool,
+}
+
+impl fmt::Display for ConfigValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.secret {
+            f.write_str(REDACTED)
+        } else {
+            f.write_str(&self.value)
+        }
+    }
+}
+
+impl fmt::Debug for ConfigValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.to_string())
+    }
+}
+
+/// The values of the configuration at one point in time.
+#[derive(Clone, Debug, Default)]
+pub struct ConfigValues(HashMap<String, ConfigValue>);
+
+impl ConfigValues {
+    /// Resolve the values of `sources`. When a key is set by several
+    /// sources, the value of the first one wins, and it is secret if any
+    /// of them holds it as a secret.
+    pub fn load(sources: &[ConfigSource]) -> Result<Self, Error> {
+        let mut values: HashMap<String, ConfigValue> = HashMap::new();
+        for source in sources.iter().rev() {
+            for (key, mut value) in load_source(source)? {
+                if let Some(previous) = values.get(&key) {
+                    value.secret |= previous.secret;
+                }
+                values.insert(key, value);
+            }
+        }
+
+        Ok(Self(values))
+    }
+
+    /// Get the value of `key`.
+    pub fn get(&self, key: &str) -> Option<&ConfigValue> {
+        self.0.get(key)
+    }
+
+    /// Replace the secret values that appear in `text`.
+    pub fn redact(&self, text: &str) -> String {
+        let mut text = text.to_string();
+        for value in self.0.values() {
+            if value.secret && !value.value.is_empty() {
+                text = text.replace(&value.value, REDACTED);
+            }
+        }
+        text
+    }
+}
+
+fn load_source(source: &ConfigSource) -> Result<Vec<(String, ConfigValue)>, Error> {
+    let plain = |(key, value): (String, String)| {
+        (
+            key,
+            ConfigValue {
+                value,
+                secret: false,
+            },
+        )
+    };
+
+    match source {
+        ConfigSource::Values(values) => Ok(values.iter().cloned().map(plain).collect()),
+        ConfigSource::File(path) => {
+            let text = fs::read_to_string(path)
+                .with_context(|| format!("failed to read config file '{}'", path.display()))?;
+            let table: toml::value::Table = toml::from_str(&text)
+                .with_context(|| format!("failed to parse config file '{}'", path.display()))?;
+
+            let mut values = Vec::new();
+            flatten("", table, &mut values)
+                .with_context(|| format!("invalid config file '{}'", path.display()))?;
+            Ok(values.into_iter().map(plain).collect())
+        }
+        ConfigSource::SecretsDir(dir) => load_secrets(dir)
+            .with_context(|| format!("failed to read secrets from '{}'", dir.display())),
+        ConfigSource::Env { prefix } if prefix.is_empty() => {
+            anyhow::bail!("the prefix of environment variables cannot be empty")
+        }
+        // Variables that are not valid Unicode cannot be read by guests, so
+        // they are skipped rather than failing.
+        ConfigSource::Env { prefix } => Ok(std::env::vars_os()
+            .filter_map(|(name, value)| {
+                let key = name.to_str()?.strip_prefix(prefix.as_str())?.to_lowercase();
+                Some((key, value.into_string().ok()?))
+            })
+            .map(plain)
+            .collect()),
+    }
+}
+
+fn flatten(
+    prefix: &str,
+    table: toml::value::Table,
+    values: &mut Vec<(String, String)>,
+) -> Result<(), Error> {
+    for (key, value) in table {
+        let key = format!("{}{}", prefix, key);
+        let value = match value {
+            toml::Value::Table(table) => {
+                flatten(&format!("{}.", key), table, values)?;
+                continue;
+            }
+            toml::Value::String(s) => s,
+            toml::Value::Integer(i) => i.to_string(),
+            toml::Value::Float(f) => f.to_string(),
+            toml::Value::Boolean(b) => b.to_string(),
+            toml::Value::Datetime(d) => d.to_string(),
+            toml::Value::Array(_) => anyhow::bail!("`{}` is an array, which is not supported", key),
+        };
+        values.push((key, value));
+    }
+
+    Ok(())
+}
+
+fn load_secrets(dir: &Path) -> Result<Vec<(String, ConfigValue)>, Error> {
+    let mut secrets = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let key = entry.file_name().to_string_lossy().into_owned();
+        // Skip hidden files, like the `..data` links of Kubernetes secret volumes.
+        if key.starts_with('.') || !entry.path().is_file() {
+            continue;
+        }
+
+        let value = fs::read_to_string(entry.path())
+            .with_context(|| format!("secret '{}' is not valid UTF-8", key))?;
+        let value = value
+            .strip_suffix('\n')
+            .map(|v| v.strip_suffix('\r').unwrap_or(v))
+            .unwrap_or(&value)
+            .to_string();
+        secrets.push((
+            key,
+            ConfigValue {
+                value,
+                secret: true,
+            },
+        ));
+    }
+
+    Ok(secrets)
+}
+
+/// Configuration made available to guests, which can be reloaded while
+/// the engine keeps running.
+#[derive(Clone)]
+pub struct RuntimeConfig {
+    sources: Arc<Vec<ConfigSource>>,
+    values: Reloadable<Arc<ConfigValues>>,
+}
+
+impl RuntimeConfig {
+    /// Load the configuration from `sources`, in order of precedence.
+    pub fn new(sources: Vec<ConfigSource>) -> Result<Self, Error> {
+        let sources = Arc::new(sources);
+        let values = {
+            let sources = sources.clone();
+            Reloadable::new(move || Ok(Arc::new(ConfigValues::load(&sources)?)))?
+        };
+
+        Ok(Self { sources, values })
+    }
+
+    /// Get the current values.
+    pub fn values(&self) -> Arc<ConfigValues> {
+        self.values.current()
+    }
+
+    /// Load the values again. If a source cannot be read, the error is
+    /// logged and the previous values are kept.
+    pub fn reload(&self) -> bool {
+        self.values.reload()
+    }
+
+    /// Reload the values in the background whenever a config file or a
+    /// file in a secrets directory changes, checking for changes every
+    /// `interval`.
+    pub fn watch(&self, interval: Duration) {
+        for source in self.sources.iter() {
+            match source {
+                ConfigSource::File(path) | ConfigSource::SecretsDir(path) => {
+                    self.values.watch(path.clone(), interval)
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+impl<T> HostCapability<T> for RuntimeConfig {
+    fn add_to_linker(&self, linker: &mut Linker<Context<T>>) -> Result<(), Error> {
+        glass_config_v01::add_to_linker(linker, |host| host.get_mut::<ConfigHost>().unwrap())?;
+        Ok(())
+    }
+
+    fn init_store(&self, ctx: &mut Context<T>) -> Result<(), Error> {
+        ctx.insert(ConfigHost(self.values()));
+        Ok(())
+    }
+}
+
+/// Values of the configuration seen by an instance.
+struct ConfigHost(Arc<ConfigValues>);
+
+impl GlassConfigV01 for ConfigHost {
+    fn get(&mut self, key: &str) -> Option<String> {
+        let value = self.0.get(key);
+        match value {
+            Some(value) => log::debug!("Guest read config key '{}': {}", key, value),
+            None => log::debug!("Guest read unset config key '{}'", key),
+        }
+
+        value.map(|v| v.value.clone())
+    }
+}
@@ -0,0 +1,137 @@
+use super::digest::{read_cached, sha256_hex, validate_sha256, write_cached};
+use ::bindle::{client::Client, Parcel};
+use anyhow::{Context, Error};
+use std::{
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
+/// Media type of the entrypoint parcel.
+pub const WASM_MEDIA_TYPE: &str = "application/wasm";
+
+/// Feature group and name flagging parcels that should be made
+/// available to the guest as files, e.g. `feature.glass.file = "true"`.
+pub const ASSET_FEATURE_GROUP: &str = "glass";
+pub const ASSET_FEATURE_NAME: &str = "file";
+
+/// The entrypoint module and assets of a bindle, fetched to the local cache.
+#[derive(Debug)]
+pub struct BindleModule {
+    /// Path to the entrypoint module.
+    pub entrypoint: PathBuf,
+    /// Directory containing the asset parcels, laid out using their names.
+    pub assets: Option<PathBuf>,
+}
+
+/// Fetch the entrypoint and asset parcels of bindle `id` from `server`,
+/// verifying their digests and caching them in `cache_dir`.
+///
+/// The entrypoint is the first WebAssembly parcel that is not a member of
+/// any group.
+pub async fn fetch(server: &str, id: &str, cache_dir: &Path) -> Result<BindleModule, Error> {
+    let client = Client::new(server)?;
+    let invoice = client
+        .get_invoice(id)
+        .await
+        .with_context(|| format!("failed to fetch invoice for bindle '{}'", id))?;
+    let parcels = invoice.parcel.unwrap_or_default();
+
+    let entrypoint = parcels
+        .iter()
+        .find(|p| is_entrypoint(p))
+        .ok_or_else(|| anyhow::anyhow!("bindle '{}' has no entrypoint module", id))?;
+    let parcels_dir = cache_dir.join("parcels");
+    let entrypoint = fetch_parcel(&client, id, entrypoint, &parcels_dir).await?;
+
+    let mut assets = None;
+    for parcel in parcels.iter().filter(|p| is_asset(p)) {
+        let dir = assets.get_or_insert_with(|| cache_dir.join("assets").join(sanitize(id)));
+        let dest = dir.join(asset_path(&parcel.label.name)?);
+        let source = fetch_parcel(&client, id, parcel, &parcels_dir).await?;
+
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(&source, &dest)
+            .with_context(|| format!("failed to copy asset '{}'", parcel.label.name))?;
+    }
+
+    log::info!("Fetched bindle {} from {}", id, server);
+    Ok(BindleModule { entrypoint, assets })
+}
+
+/// Fetch a parcel into `dir`, unless a verified copy is already cached there.
+async fn fetch_parcel(
+    client: &Client,
+    id: &str,
+    parcel: &Parcel,
+    dir: &Path,
+) -> Result<PathBuf, Error> {
+    let sha = &parcel.label.sha256;
+    validate_sha256(sha)
+        .with_context(|| format!("invalid digest for parcel '{}'", parcel.label.name))?;
+    if read_cached(dir, sha).is_some() {
+        log::debug!("Using cached parcel {}", sha);
+        return Ok(dir.join(sha));
+    }
+
+    let bytes = client
+        .get_parcel(id, sha)
+        .await
+        .with_context(|| format!("failed to fetch parcel '{}'", parcel.label.name))?;
+    let digest = sha256_hex(&bytes);
+    anyhow::ensure!(
+        &digest == sha,
+        "digest mismatch for parcel '{}': expected {}, got {}",
+        parcel.label.name,
+        sha,
+        digest
+    );
+
+    // Parcels are only cached once verified.
+    write_cached(dir, sha, &bytes)
+}
+
+fn is_entrypoint(parcel: &Parcel) -> bool {
+    let grouped = parcel
+        .conditions
+        .as_ref()
+        .and_then(|c| c.member_of.as_ref())
+        .map_or(false, |groups| !groups.is_empty());
+
+    parcel.label.media_type == WASM_MEDIA_TYPE && !grouped
+}
+
+fn is_asset(parcel: &Parcel) -> bool {
+    parcel
+        .label
+        .feature
+        .as_ref()
+        .and_then(|f| f.get(ASSET_FEATURE_GROUP))
+        .and_then(|f| f.get(ASSET_FEATURE_NAME))
+        .map_or(false, |v| v == "true")
+}
+
+/// Only allow relative asset names that stay within the assets directory.
+fn asset_path(name: &str) -> Result<PathBuf, Error> {
+    let path = PathBuf::from(name);
+    anyhow::ensure!(
+        path.components().all(|c| matches!(c, Component::Normal(_))),
+        "invalid asset name '{}'",
+        name
+    );
+
+    Ok(path)
+}
+
+fn sanitize(id: &str) -> String {
+    id.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,57 @@
+use anyhow::{Context, Error};
+use sha2::{Digest, Sha256};
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
+use tempfile::NamedTempFile;
+
+/// Hex encoded SHA-256 digest of `bytes`.
+pub(super) fn sha256_hex(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+/// Check that `sha` is a hex encoded SHA-256 digest, so it can safely be
+/// used as a file name in the cache.
+pub(super) fn validate_sha256(sha: &str) -> Result<(), Error> {
+    anyhow::ensure!(
+        sha.len() == 64 && sha.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f')),
+        "invalid SHA-256 digest '{}'",
+        sha
+    );
+
+    Ok(())
+}
+
+/// Read the content with digest `sha` cached in `dir`, if any.
+///
+/// Cached files are hashed again, and any that no longer match their digest
+/// are treated as missing so they get fetched again.
+pub(super) fn read_cached(dir: &Path, sha: &str) -> Option<Vec<u8>> {
+    let path = dir.join(sha);
+    let bytes = fs::read(&path).ok()?;
+    if sha256_hex(&bytes) != sha {
+        log::warn!("Ignoring corrupted cache entry {}", path.display());
+        return None;
+    }
+
+    Some(bytes)
+}
+
+/// Cache verified content with digest `sha` in `dir`, returning its path.
+///
+/// The content is written to a unique temporary file first, so a partially
+/// written file is never mistaken for a valid one, even when several
+/// processes share the cache.
+pub(super) fn write_cached(dir: &Path, sha: &str, bytes: &[u8]) -> Result<PathBuf, Error> {
+    fs::create_dir_all(dir)?;
+    let mut tmp = NamedTempFile::new_in(dir)?;
+    tmp.write_all(bytes)?;
+
+    let path = dir.join(sha);
+    tmp.persist(&path)
+        .with_context(|| format!("failed to write {}", path.display()))?;
+
+    Ok(path)
+}
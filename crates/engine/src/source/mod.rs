@@ -0,0 +1,43 @@
+//! Sources the entrypoint module can be loaded from.
+
+pub mod bindle;
+mod digest;
+pub mod oci;
+
+use anyhow::{Context, Error};
+use std::{fmt, sync::Arc};
+
+/// The entrypoint module of a `WasiExecutionContext`.
+#[derive(Clone, Debug)]
+pub enum ModuleSource {
+    /// A module on the local filesystem.
+    File(String),
+    /// A module already loaded in memory, e.g. after pulling it from a registry.
+    Bytes { name: String, bytes: Arc<Vec<u8>> },
+}
+
+impl ModuleSource {
+    /// Name identifying the module in logs.
+    pub fn name(&self) -> &str {
+        match self {
+            ModuleSource::File(path) => path,
+            ModuleSource::Bytes { name, .. } => name,
+        }
+    }
+
+    /// Read the contents of the module.
+    pub fn read(&self) -> Result<Vec<u8>, Error> {
+        match self {
+            ModuleSource::File(path) => {
+                std::fs::read(path).with_context(|| format!("failed to read module '{}'", path))
+            }
+            ModuleSource::Bytes { bytes, .. } => Ok(bytes.to_vec()),
+        }
+    }
+}
+
+impl fmt::Display for ModuleSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
@@ -0,0 +1,250 @@
+use super::digest::{read_cached, sha256_hex, validate_sha256, write_cached};
+use anyhow::{Context, Error};
+use reqwest::{header, Client, RequestBuilder, Response, StatusCode};
+use serde::Deserialize;
+use std::path::Path;
+
+/// Scheme prefix of OCI references, e.g. `oci://ghcr.io/deislabs/example:v1`.
+pub const OCI_SCHEME: &str = "oci://";
+
+/// Media types of layers containing a WebAssembly module.
+pub const WASM_LAYER_MEDIA_TYPES: &[&str] = &[
+    "application/vnd.wasm.content.layer.v1+wasm",
+    "application/vnd.module.wasm.content.layer.v1+wasm",
+];
+
+const MANIFEST_MEDIA_TYPES: &str =
+    "application/vnd.oci.image.manifest.v1+json, application/vnd.docker.distribution.manifest.v2+json";
+
+/// A reference to an artifact in an OCI registry.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Reference {
+    pub registry: String,
+    pub repository: String,
+    /// Either a tag or a digest.
+    pub reference: String,
+}
+
+impl Reference {
+    /// Parse a reference of the form `[oci://]registry/repository[:tag|@digest]`.
+    /// References without a tag or digest point to the `latest` tag.
+    pub fn parse(s: &str) -> Result<Self, Error> {
+        let s = s.strip_prefix(OCI_SCHEME).unwrap_or(s);
+        let (registry, rest) = s
+            .split_once('/')
+            .ok_or_else(|| anyhow::anyhow!("invalid OCI reference '{}': missing repository", s))?;
+
+        let (repository, reference) = match rest.split_once('@') {
+            Some((repository, digest)) => (repository, digest),
+            None => rest.rsplit_once(':').unwrap_or((rest, "latest")),
+        };
+        anyhow::ensure!(
+            !registry.is_empty() && !repository.is_empty() && !reference.is_empty(),
+            "invalid OCI reference '{}'",
+            s
+        );
+
+        Ok(Self {
+            registry: registry.to_string(),
+            repository: repository.to_string(),
+            reference: reference.to_string(),
+        })
+    }
+
+    /// The hex encoded SHA-256 digest of the manifest, if the reference is
+    /// a digest rather than a tag.
+    pub fn digest(&self) -> Result<Option<&str>, Error> {
+        if !self.reference.contains(':') {
+            return Ok(None);
+        }
+
+        let digest = self.reference.strip_prefix("sha256:").ok_or_else(|| {
+            anyhow::anyhow!("unsupported digest algorithm for '{}'", self.reference)
+        })?;
+        validate_sha256(digest)?;
+
+        Ok(Some(digest))
+    }
+}
+
+#[derive(Deserialize)]
+struct Manifest {
+    layers: Vec<Descriptor>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Descriptor {
+    media_type: String,
+    digest: String,
+}
+
+#[derive(Deserialize)]
+struct Token {
+    #[serde(alias = "access_token")]
+    token: String,
+}
+
+/// Pull the WebAssembly module referenced by `reference`, verifying its digest
+/// and caching it in `cache_dir` by digest.
+///
+/// When `insecure` is set, the registry is accessed over plain HTTP.
+pub async fn pull(reference: &str, cache_dir: &Path, insecure: bool) -> Result<Vec<u8>, Error> {
+    let reference = Reference::parse(reference)?;
+    let scheme = if insecure { "http" } else { "https" };
+    let base = format!(
+        "{}://{}/v2/{}",
+        scheme, reference.registry, reference.repository
+    );
+    let client = Client::new();
+
+    let manifest = send(
+        client
+            .get(format!("{}/manifests/{}", base, reference.reference))
+            .header(header::ACCEPT, MANIFEST_MEDIA_TYPES),
+        &client,
+    )
+    .await
+    .with_context(|| format!("failed to pull manifest for '{}'", reference.repository))?
+    .bytes()
+    .await?;
+    if let Some(expected) = reference.digest()? {
+        let digest = sha256_hex(&manifest);
+        anyhow::ensure!(
+            digest == expected,
+            "digest mismatch for manifest: expected {}, got sha256:{}",
+            reference.reference,
+            digest
+        );
+    }
+    let manifest: Manifest = serde_json::from_slice(&manifest)?;
+
+    let layer = manifest
+        .layers
+        .iter()
+        .find(|l| WASM_LAYER_MEDIA_TYPES.contains(&l.media_type.as_str()))
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "'{}:{}' has no WebAssembly layer",
+                reference.repository,
+                reference.reference
+            )
+        })?;
+    let expected = layer.digest.strip_prefix("sha256:").ok_or_else(|| {
+        anyhow::anyhow!("unsupported digest algorithm for layer {}", layer.digest)
+    })?;
+    validate_sha256(expected).context("invalid layer digest")?;
+
+    if let Some(bytes) = read_cached(cache_dir, expected) {
+        log::debug!("Using cached layer {}", layer.digest);
+        return Ok(bytes);
+    }
+
+    let bytes = send(
+        client.get(format!("{}/blobs/{}", base, layer.digest)),
+        &client,
+    )
+    .await
+    .with_context(|| format!("failed to pull layer {}", layer.digest))?
+    .bytes()
+    .await?
+    .to_vec();
+
+    let digest = sha256_hex(&bytes);
+    anyhow::ensure!(
+        digest == expected,
+        "digest mismatch for layer: expected {}, got sha256:{}",
+        layer.digest,
+        digest
+    );
+
+    // Layers are only cached once verified.
+    write_cached(cache_dir, expected, &bytes)?;
+
+    log::info!(
+        "Pulled {}/{}:{}",
+        reference.registry,
+        reference.repository,
+        reference.reference
+    );
+    Ok(bytes)
+}
+
+/// Send a request, authenticating anonymously if the registry requires
+/// a bearer token.
+async fn send(req: RequestBuilder, client: &Client) -> Result<Response, Error> {
+    let retry = req.try_clone();
+    let res = req.send().await?;
+    if res.status() != StatusCode::UNAUTHORIZED {
+        return Ok(res.error_for_status()?);
+    }
+
+    let (retry, challenge) = match (retry, res.headers().get(header::WWW_AUTHENTICATE)) {
+        (Some(retry), Some(challenge)) => (retry, challenge.to_str()?.to_string()),
+        _ => return Ok(res.error_for_status()?),
+    };
+    let token = anonymous_token(client, &challenge).await?;
+
+    Ok(retry.bearer_auth(token).send().await?.error_for_status()?)
+}
+
+/// Request an anonymous token for a `Bearer realm="..",service="..",scope=".."`
+/// challenge.
+async fn anonymous_token(client: &Client, challenge: &str) -> Result<String, Error> {
+    let params = challenge
+        .strip_prefix("Bearer ")
+        .ok_or_else(|| anyhow::anyhow!("unsupported authentication challenge '{}'", challenge))?;
+
+    let mut realm = None;
+    let mut query = Vec::new();
+    for (k, v) in challenge_params(params) {
+        match k.as_str() {
+            "realm" => realm = Some(v),
+            _ => query.push((k, v)),
+        }
+    }
+    let realm = realm.ok_or_else(|| anyhow::anyhow!("authentication challenge has no realm"))?;
+
+    let token: Token = client
+        .get(realm)
+        .query(&query)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    Ok(token.token)
+}
+
+/// Parse the comma separated `key=value` parameters of a challenge, where
+/// values can be quoted strings containing commas and escaped characters.
+fn challenge_params(s: &str) -> Vec<(String, String)> {
+    let mut params = Vec::new();
+    let mut chars = s.chars().peekable();
+    loop {
+        while chars.next_if(|c| *c == ',' || c.is_whitespace()).is_some() {}
+        let key: String = chars.by_ref().take_while(|c| *c != '=').collect();
+        if key.is_empty() {
+            return params;
+        }
+
+        let mut value = String::new();
+        if chars.next_if_eq(&'"').is_some() {
+            while let Some(c) = chars.next() {
+                match c {
+                    '"' => break,
+                    '\\' => value.extend(chars.next()),
+                    c => value.push(c),
+                }
+            }
+        } else {
+            while let Some(c) = chars.next_if(|c| *c != ',') {
+                value.push(c);
+            }
+            value.truncate(value.trim_end().len());
+        }
+
+        params.push((key.trim().to_string(), value));
+    }
+}
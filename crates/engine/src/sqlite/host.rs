@@ -0,0 +1,222 @@
+use super::SqliteDatabase;
+use crate::{capability::HostCapability, Config, Context};
+use anyhow::Error;
+use glass_sqlite_v01::{GlassSqliteV01, Rows, SqliteError, ValueParam, ValueResult};
+use rusqlite::{
+    hooks::{AuthAction, AuthContext, Authorization},
+    limits::Limit,
+    params_from_iter,
+    types::Value,
+    Connection, ErrorCode, OpenFlags,
+};
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use wasmtime::Linker;
+
+witx_bindgen_wasmtime::import!("crates/engine/glass_sqlite_v01.witx");
+
+/// Number of SQLite virtual machine instructions between two checks of
+/// the statement timeout.
+const TIMEOUT_CHECK_INTERVAL: i32 = 1000;
+
+/// The SQLite host interface, giving access to the databases configured
+/// on the engine.
+pub struct Sqlite {
+    databases: Arc<HashMap<String, SqliteDatabase>>,
+    timeout: Option<Duration>,
+}
+
+impl Sqlite {
+    pub fn new(config: &Config) -> Self {
+        let databases = config
+            .sqlite_databases
+            .iter()
+            .map(|db| (db.name.clone(), db.clone()))
+            .collect();
+
+        Self {
+            databases: Arc::new(databases),
+            timeout: config.sqlite_timeout,
+        }
+    }
+}
+
+impl<T> HostCapability<T> for Sqlite {
+    fn add_to_linker(&self, linker: &mut Linker<Context<T>>) -> Result<(), Error> {
+        glass_sqlite_v01::add_to_linker(linker, |host| host.get_mut::<SqliteHost>().unwrap())?;
+        Ok(())
+    }
+
+    fn init_store(&self, ctx: &mut Context<T>) -> Result<(), Error> {
+        ctx.insert(SqliteHost {
+            databases: self.databases.clone(),
+            timeout: self.timeout,
+            connections: Vec::new(),
+        });
+        Ok(())
+    }
+}
+
+/// Databases opened by an instance, which are closed with its store.
+struct SqliteHost {
+    databases: Arc<HashMap<String, SqliteDatabase>>,
+    timeout: Option<Duration>,
+    connections: Vec<Option<Connection>>,
+}
+
+impl SqliteHost {
+    /// Run `f` on an open database, interrupting it once the statement
+    /// timeout elapses.
+    fn run<R>(
+        &self,
+        db: u32,
+        f: impl FnOnce(&Connection) -> rusqlite::Result<R>,
+    ) -> Result<R, SqliteError> {
+        let conn = self
+            .connections
+            .get(db as usize)
+            .and_then(Option::as_ref)
+            .ok_or(SqliteError::InvalidDatabase)?;
+
+        if let Some(timeout) = self.timeout {
+            let deadline = Instant::now() + timeout;
+            conn.progress_handler(
+                TIMEOUT_CHECK_INTERVAL,
+                Some(move || Instant::now() > deadline),
+            );
+        }
+        let res = f(conn);
+        conn.progress_handler(TIMEOUT_CHECK_INTERVAL, None::<fn() -> bool>);
+
+        res.map_err(sqlite_error)
+    }
+}
+
+impl GlassSqliteV01 for SqliteHost {
+    fn open(&mut self, name: &str) -> Result<u32, SqliteError> {
+        let database = self
+            .databases
+            .get(name)
+            .ok_or(SqliteError::NoSuchDatabase)?;
+        let flags = if database.read_only {
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX
+        } else {
+            OpenFlags::default()
+        };
+
+        let conn = Connection::open_with_flags(&database.path, flags).map_err(|e| {
+            log::warn!(
+                "Cannot open SQLite database '{}' at '{}': {}",
+                name,
+                database.path.display(),
+                e
+            );
+            SqliteError::Failed
+        })?;
+        confine(&conn);
+        self.connections.push(Some(conn));
+
+        Ok((self.connections.len() - 1) as u32)
+    }
+
+    fn execute(
+        &mut self,
+        db: u32,
+        statement: &str,
+        params: Vec<ValueParam<'_>>,
+    ) -> Result<u64, SqliteError> {
+        let params = params_from_iter(params.iter().map(to_sql));
+        let changed = self.run(db, |conn| conn.execute(statement, params))?;
+
+        Ok(changed as u64)
+    }
+
+    fn query(
+        &mut self,
+        db: u32,
+        statement: &str,
+        params: Vec<ValueParam<'_>>,
+    ) -> Result<Rows, SqliteError> {
+        let params = params_from_iter(params.iter().map(to_sql));
+        self.run(db, |conn| {
+            let mut stmt = conn.prepare(statement)?;
+            let columns: Vec<String> = stmt
+                .column_names()
+                .into_iter()
+                .map(str::to_string)
+                .collect();
+
+            let mut rows = Vec::new();
+            let mut selected = stmt.query(params)?;
+            while let Some(row) = selected.next()? {
+                let values = (0..columns.len())
+                    .map(|i| row.get::<_, Value>(i).map(from_sql))
+                    .collect::<rusqlite::Result<_>>()?;
+                rows.push(values);
+            }
+
+            Ok(Rows { columns, rows })
+        })
+    }
+
+    fn close(&mut self, db: u32) {
+        if let Some(conn) = self.connections.get_mut(db as usize) {
+            *conn = None;
+        }
+    }
+}
+
+/// Keep the statements of guests to the database they opened, so they
+/// cannot read or create other files of the host.
+fn confine(conn: &Connection) {
+    conn.set_limit(Limit::SQLITE_LIMIT_ATTACHED, 0);
+    // `VACUUM INTO` attaches its target, so it is denied as well.
+    conn.authorizer(Some(|ctx: AuthContext<'_>| match ctx.action {
+        AuthAction::Attach { .. } | AuthAction::Detach { .. } => Authorization::Deny,
+        _ => Authorization::Allow,
+    }));
+}
+
+fn to_sql(value: &ValueParam<'_>) -> Value {
+    match value {
+        ValueParam::Null => Value::Null,
+        ValueParam::Integer(i) => Value::Integer(*i),
+        ValueParam::Real(f) => Value::Real(*f),
+        ValueParam::Text(s) => Value::Text(s.to_string()),
+        ValueParam::Blob(b) => Value::Blob(b.to_vec()),
+    }
+}
+
+fn from_sql(value: Value) -> ValueResult {
+    match value {
+        Value::Null => ValueResult::Null,
+        Value::Integer(i) => ValueResult::Integer(i),
+        Value::Real(f) => ValueResult::Real(f),
+        Value::Text(s) => ValueResult::Text(s),
+        Value::Blob(b) => ValueResult::Blob(b),
+    }
+}
+
+fn sqlite_error(e: rusqlite::Error) -> SqliteError {
+    let err = match &e {
+        rusqlite::Error::SqliteFailure(err, _) => match err.code {
+            ErrorCode::OperationInterrupted => SqliteError::Timeout,
+            ErrorCode::ReadOnly => SqliteError::ReadOnly,
+            ErrorCode::AuthorizationForStatementDenied => SqliteError::Denied,
+            ErrorCode::ConstraintViolation => SqliteError::ConstraintViolation,
+            // `SQLITE_ERROR`, which SQLite returns for invalid statements.
+            ErrorCode::Unknown => SqliteError::InvalidQuery,
+            _ => SqliteError::Failed,
+        },
+        rusqlite::Error::InvalidParameterCount(..)
+        | rusqlite::Error::ExecuteReturnedResults
+        | rusqlite::Error::MultipleStatement => SqliteError::InvalidQuery,
+        _ => SqliteError::Failed,
+    };
+
+    log::warn!("SQLite error: {}", e);
+    err
+}
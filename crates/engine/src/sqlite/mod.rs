@@ -0,0 +1,40 @@
+//! SQLite databases made available to guests through `glass_sqlite_v01`.
+//!
+//! Guests open databases by the name they are configured with on the host,
+//! and never get access to the database files themselves.
+
+#[cfg(feature = "sqlite")]
+mod host;
+
+#[cfg(feature = "sqlite")]
+pub use host::Sqlite;
+
+use anyhow::Error;
+use std::{path::PathBuf, str::FromStr};
+
+/// A database guests can open.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SqliteDatabase {
+    /// Name guests open the database by.
+    pub name: String,
+    /// Path of the database file on the host.
+    pub path: PathBuf,
+    /// Whether statements writing to the database are rejected.
+    pub read_only: bool,
+}
+
+impl FromStr for SqliteDatabase {
+    type Err = Error;
+
+    /// Parse `NAME=PATH`, for a database that is not read-only.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once('=') {
+            Some((name, path)) if !name.is_empty() && !path.is_empty() => Ok(Self {
+                name: name.to_string(),
+                path: PathBuf::from(path),
+                read_only: false,
+            }),
+            _ => anyhow::bail!("must be of the form `name=path`"),
+        }
+    }
+}
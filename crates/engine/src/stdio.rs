@@ -0,0 +1,189 @@
+use crate::runtime_config::ConfigValues;
+use anyhow::Error;
+use std::{
+    fmt,
+    io::{self, Write},
+    str::FromStr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+use wasi_common::{pipe::WritePipe, WasiFile};
+
+/// Size of the buffers of captured streams, unless configured otherwise.
+pub const DEFAULT_CAPTURE_BYTES: usize = 64 * 1024;
+
+static NEXT_INVOCATION_ID: AtomicU64 = AtomicU64::new(1);
+
+/// What happens to the output a guest writes to stdout or stderr.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StdioPolicy {
+    /// Write to the stream of the host process.
+    Inherit,
+    /// Drop the output.
+    Discard,
+    /// Buffer up to `max_bytes` of the output of each invocation, and
+    /// forward it to the log when the invocation completes.
+    Capture { max_bytes: usize },
+}
+
+impl Default for StdioPolicy {
+    fn default() -> Self {
+        StdioPolicy::Inherit
+    }
+}
+
+impl FromStr for StdioPolicy {
+    type Err = Error;
+
+    /// Parse `inherit`, `discard`, `capture` or `capture:<max bytes>`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "inherit" => Ok(StdioPolicy::Inherit),
+            "discard" => Ok(StdioPolicy::Discard),
+            "capture" => Ok(StdioPolicy::Capture {
+                max_bytes: DEFAULT_CAPTURE_BYTES,
+            }),
+            _ => match s.strip_prefix("capture:") {
+                Some(max_bytes) => Ok(StdioPolicy::Capture {
+                    max_bytes: max_bytes.parse()?,
+                }),
+                None => anyhow::bail!(
+                    "must be one of `inherit`, `discard`, `capture` or `capture:<max bytes>`"
+                ),
+            },
+        }
+    }
+}
+
+/// A standard stream of the guest.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Stream {
+    Stdout,
+    Stderr,
+}
+
+impl fmt::Display for Stream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Stream::Stdout => f.write_str("stdout"),
+            Stream::Stderr => f.write_str("stderr"),
+        }
+    }
+}
+
+/// Output of an invocation captured for the log, shared between the WASI
+/// file the guest writes to and the `Capture` that flushes it.
+#[derive(Clone)]
+struct CapturedOutput(Arc<Mutex<Buffer>>);
+
+struct Buffer {
+    bytes: Vec<u8>,
+    max_bytes: usize,
+    dropped: usize,
+}
+
+impl Write for CapturedOutput {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut buffer = self.0.lock().unwrap();
+        let room = buffer.max_bytes.saturating_sub(buffer.bytes.len());
+        let kept = room.min(buf.len());
+        buffer.bytes.extend_from_slice(&buf[..kept]);
+        buffer.dropped += buf.len() - kept;
+
+        // Output past the cap is dropped, not refused, so guests do not fail.
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Streams of an invocation that are captured, forwarded to the log
+/// when the invocation's store is dropped.
+pub(crate) struct Capture {
+    invocation_id: u64,
+    module: String,
+    streams: Vec<(Stream, CapturedOutput)>,
+    /// Configuration whose secret values are redacted from the output.
+    secrets: Option<Arc<ConfigValues>>,
+}
+
+impl Capture {
+    pub(crate) fn new(module: &str) -> Self {
+        Self {
+            invocation_id: NEXT_INVOCATION_ID.fetch_add(1, Ordering::Relaxed),
+            module: module.to_string(),
+            streams: Vec::new(),
+            secrets: None,
+        }
+    }
+
+    /// Redact the secret values of `config` from the captured output.
+    pub(crate) fn redact(&mut self, config: Arc<ConfigValues>) {
+        self.secrets = Some(config);
+    }
+
+    /// Get the file the guest writes `stream` to under `policy`, or `None`
+    /// if the stream of the host process is inherited.
+    pub(crate) fn file(
+        &mut self,
+        stream: Stream,
+        policy: &StdioPolicy,
+    ) -> Option<Box<dyn WasiFile>> {
+        match policy {
+            StdioPolicy::Inherit => None,
+            StdioPolicy::Discard => Some(Box::new(WritePipe::new(io::sink()))),
+            StdioPolicy::Capture { max_bytes } => {
+                let output = CapturedOutput(Arc::new(Mutex::new(Buffer {
+                    bytes: Vec::new(),
+                    max_bytes: *max_bytes,
+                    dropped: 0,
+                })));
+                self.streams.push((stream, output.clone()));
+                Some(Box::new(WritePipe::new(output)))
+            }
+        }
+    }
+}
+
+impl Drop for Capture {
+    fn drop(&mut self) {
+        for (stream, output) in &self.streams {
+            let buffer = output.0.lock().unwrap();
+            let level = match stream {
+                Stream::Stdout => log::Level::Info,
+                Stream::Stderr => log::Level::Warn,
+            };
+
+            for line in String::from_utf8_lossy(&buffer.bytes).lines() {
+                let line = match &self.secrets {
+                    Some(secrets) => secrets.redact(line),
+                    None => line.to_string(),
+                };
+                log::log!(
+                    target: "glass::guest",
+                    level,
+                    "[{} {}] {}: {}",
+                    self.invocation_id,
+                    self.module,
+                    stream,
+                    line
+                );
+            }
+            if buffer.dropped > 0 {
+                log::warn!(
+                    target: "glass::guest",
+                    "[{} {}] {}: dropped {} bytes past the {} bytes limit",
+                    self.invocation_id,
+                    self.module,
+                    stream,
+                    buffer.dropped,
+                    buffer.max_bytes
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,103 @@
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Condvar, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+use wasmtime::InterruptHandle;
+
+/// Interrupts instances running past their deadline, using a single thread
+/// for all of the deadlines of an execution context.
+pub(crate) struct Watchdog {
+    shared: Arc<Shared>,
+}
+
+#[derive(Default)]
+struct Shared {
+    state: Mutex<State>,
+    changed: Condvar,
+}
+
+#[derive(Default)]
+struct State {
+    /// Pending deadlines, keyed by when they elapse and a unique id.
+    deadlines: BTreeMap<(Instant, u64), InterruptHandle>,
+    next_id: u64,
+    stopped: bool,
+}
+
+impl Watchdog {
+    pub(crate) fn new() -> Self {
+        let shared = Arc::new(Shared::default());
+        let watched = shared.clone();
+        thread::spawn(move || watched.run());
+
+        Self { shared }
+    }
+
+    /// Interrupt the instance of `handle` once `timeout` elapses, unless the
+    /// returned deadline is dropped first.
+    pub(crate) fn start(&self, handle: InterruptHandle, timeout: Duration) -> Deadline {
+        let mut state = self.shared.state.lock().unwrap();
+        let key = (Instant::now() + timeout, state.next_id);
+        state.next_id += 1;
+        let earliest = state
+            .deadlines
+            .keys()
+            .next()
+            .map_or(true, |first| key < *first);
+        state.deadlines.insert(key, handle);
+        if earliest {
+            self.shared.changed.notify_one();
+        }
+
+        Deadline {
+            shared: self.shared.clone(),
+            key,
+        }
+    }
+}
+
+impl Drop for Watchdog {
+    fn drop(&mut self) {
+        self.shared.state.lock().unwrap().stopped = true;
+        self.shared.changed.notify_one();
+    }
+}
+
+impl Shared {
+    fn run(&self) {
+        let mut state = self.state.lock().unwrap();
+        while !state.stopped {
+            let now = Instant::now();
+            let next = state.deadlines.keys().next().copied();
+            state = match next {
+                Some(key) if key.0 <= now => {
+                    if let Some(handle) = state.deadlines.remove(&key) {
+                        handle.interrupt();
+                    }
+                    state
+                }
+                Some((at, _)) => self.changed.wait_timeout(state, at - now).unwrap().0,
+                None => self.changed.wait(state).unwrap(),
+            };
+        }
+    }
+}
+
+/// A pending deadline, cancelled when dropped.
+pub(crate) struct Deadline {
+    shared: Arc<Shared>,
+    key: (Instant, u64),
+}
+
+impl Drop for Deadline {
+    fn drop(&mut self) {
+        self.shared
+            .state
+            .lock()
+            .unwrap()
+            .deadlines
+            .remove(&self.key);
+    }
+}
@@ -0,0 +1,48 @@
+use crate::error::HttpError;
+use anyhow::Error;
+use hyper::{body::HttpBody, http::request::Parts, Body, Request};
+
+/// Maximum size of request bodies engines buffer in memory before invoking
+/// the guest. The trigger adds it to the request extensions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BodyLimit(pub usize);
+
+/// Read the whole body of a request into memory, failing with
+/// `HttpError::PayloadTooLarge` as soon as it is known to exceed the
+/// `BodyLimit` of the request.
+pub async fn to_bytes_limited(req: Request<Body>) -> Result<(Parts, Vec<u8>), Error> {
+    let limit = req.extensions().get::<BodyLimit>().map(|l| l.0);
+    let (parts, body) = req.into_parts();
+    Ok((parts, read_limited(body, limit).await?))
+}
+
+async fn read_limited(mut body: Body, limit: Option<usize>) -> Result<Vec<u8>, Error> {
+    let limit = match limit {
+        Some(limit) => limit,
+        None => return Ok(hyper::body::to_bytes(body).await?.to_vec()),
+    };
+
+    // The lower bound is the Content-Length, when the client sent one.
+    if body.size_hint().lower() > limit as u64 {
+        return Err(too_large(limit));
+    }
+
+    let mut bytes = Vec::new();
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk?;
+        if bytes.len() + chunk.len() > limit {
+            return Err(too_large(limit));
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+
+    Ok(bytes)
+}
+
+fn too_large(limit: usize) -> Error {
+    HttpError::PayloadTooLarge(anyhow::anyhow!(
+        "request body is larger than {} bytes",
+        limit
+    ))
+    .into()
+}
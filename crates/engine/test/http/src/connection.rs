@@ -0,0 +1,125 @@
+use hyper::{header::HeaderMap, Version};
+use ipnet::IpNet;
+use std::net::{IpAddr, SocketAddr};
+
+/// Prefix of the synthetic headers describing the connection. Headers with
+/// this prefix sent by clients are removed, so guests can trust them.
+pub const HEADER_PREFIX: &str = "x-glass-";
+
+const FORWARDED_FOR: &str = "x-forwarded-for";
+const FORWARDED_PROTO: &str = "x-forwarded-proto";
+
+/// Metadata of the connection a request was received on.
+///
+/// The trigger adds it to the request extensions, and engines pass it
+/// to the guest.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConnectionInfo {
+    /// Address of the client. When the peer is a trusted proxy, this is
+    /// the address it forwarded the request for.
+    pub remote_addr: IpAddr,
+    /// Address of the peer of the connection.
+    pub peer_addr: SocketAddr,
+    /// Address the connection was accepted on.
+    pub local_addr: SocketAddr,
+    /// Either `http` or `https`.
+    pub scheme: String,
+}
+
+impl ConnectionInfo {
+    /// Get the connection info of a request received from `peer_addr`.
+    ///
+    /// `X-Forwarded-For` and `X-Forwarded-Proto` are only honored if the
+    /// peer is one of the `trusted_proxies`. The client is then the last
+    /// forwarded address that is not itself a trusted proxy.
+    pub fn resolve(
+        headers: &HeaderMap,
+        peer_addr: SocketAddr,
+        local_addr: SocketAddr,
+        tls: bool,
+        trusted_proxies: &[IpNet],
+    ) -> Self {
+        let trusted = |ip: &IpAddr| trusted_proxies.iter().any(|net| net.contains(ip));
+
+        let mut remote_addr = peer_addr.ip();
+        let mut scheme = if tls { "https" } else { "http" }.to_string();
+        if trusted(&remote_addr) {
+            let forwarded: Vec<Option<IpAddr>> = headers
+                .get_all(FORWARDED_FOR)
+                .iter()
+                .flat_map(|v| match v.to_str() {
+                    Ok(v) => v.split(',').map(parse_forwarded).collect(),
+                    Err(_) => vec![None],
+                })
+                .collect();
+            // Entries left of one that cannot be parsed are not trusted, as
+            // the proxy that added it may not have appended the others.
+            for ip in forwarded.into_iter().rev() {
+                let ip = match ip {
+                    Some(ip) => ip,
+                    None => break,
+                };
+                remote_addr = ip;
+                if !trusted(&ip) {
+                    break;
+                }
+            }
+
+            if let Some(proto) = headers.get(FORWARDED_PROTO).and_then(|v| v.to_str().ok()) {
+                if proto == "http" || proto == "https" {
+                    scheme = proto.to_string();
+                }
+            }
+        }
+
+        Self {
+            remote_addr,
+            peer_addr,
+            local_addr,
+            scheme,
+        }
+    }
+
+    /// Synthetic headers describing the connection, for interfaces that
+    /// have no field for it.
+    pub fn headers(&self, version: Version) -> Vec<(String, String)> {
+        vec![
+            ("remote-addr", self.remote_addr.to_string()),
+            ("peer-addr", self.peer_addr.to_string()),
+            ("local-addr", self.local_addr.to_string()),
+            ("scheme", self.scheme.clone()),
+            ("http-version", format!("{:?}", version)),
+        ]
+        .into_iter()
+        .map(|(name, value)| (format!("{}{}", HEADER_PREFIX, name), value))
+        .collect()
+    }
+}
+
+/// Remove the headers clients are not allowed to set themselves.
+pub fn strip_synthetic_headers(headers: &mut HeaderMap) {
+    let names: Vec<_> = headers
+        .keys()
+        .filter(|name| name.as_str().starts_with(HEADER_PREFIX))
+        .cloned()
+        .collect();
+    for name in names {
+        headers.remove(name);
+    }
+}
+
+/// Parse an `X-Forwarded-For` entry, which is an IP address optionally
+/// followed by a port, with IPv6 addresses in brackets when it is.
+fn parse_forwarded(entry: &str) -> Option<IpAddr> {
+    let entry = entry.trim();
+    entry
+        .parse()
+        .ok()
+        .or_else(|| entry.parse::<SocketAddr>().ok().map(|addr| addr.ip()))
+        .or_else(|| {
+            entry
+                .strip_prefix('[')
+                .and_then(|e| e.strip_suffix(']'))
+                .and_then(|ip| ip.parse().ok())
+        })
+}
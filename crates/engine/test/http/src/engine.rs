@@ -1,15 +1,20 @@
-use crate::trigger::HttpEngine;
+use crate::{
+    body::to_bytes_limited,
+    connection::ConnectionInfo,
+    error::HttpError,
+    params::{request_params, serialize_param},
+    trigger::HttpEngine,
+};
 use anyhow::Error;
 use async_trait::async_trait;
 use deislabs_http_v01::{DeislabsHttpV01, DeislabsHttpV01Data, Method};
+use glass_engine::{Config, ModuleSource, WasiExecutionContextBuilder};
 use hyper::{Body, Request, Response};
 use std::{str::FromStr, sync::Arc, time::Instant};
-use wasmtime::{Instance, Store};
 
 witx_bindgen_wasmtime::export!("crates/engine/test/http/deislabs_http_v01.witx");
 
 type WasiExecutionContext = glass_engine::WasiExecutionContext<DeislabsHttpV01Data>;
-type DataContext = glass_engine::Context<DeislabsHttpV01Data>;
 
 #[derive(Clone)]
 pub struct Engine(pub Arc<WasiExecutionContext>);
@@ -21,78 +26,135 @@ impl HttpEngine for Engine {
         req: hyper::Request<hyper::Body>,
     ) -> Result<hyper::Response<hyper::Body>, Error> {
         let start = Instant::now();
-        let (store, instance) = self.0.prepare_exec(None)?;
-        let res = self.execute_impl(store, instance, req).await?;
+        let res = self.execute_impl(req).await?;
         log::info!("Total request execution time: {:#?}", start.elapsed());
         Ok(res)
     }
 }
 
 impl Engine {
-    async fn execute_impl(
-        &self,
-        mut store: Store<DataContext>,
-        instance: Instance,
-        req: Request<Body>,
-    ) -> Result<Response<Body>, Error> {
-        let r = DeislabsHttpV01::new(&mut store, &instance, |host| {
-            host.runtime_data.as_mut().unwrap()
-        })?;
+    /// Name of the interface implemented by modules this engine executes.
+    pub const INTERFACE: &'static str = "deislabs_http_v01";
+    /// Functions modules must export to implement the interface.
+    pub const EXPORTS: &'static [&'static str] = &["handler"];
 
+    /// Build an engine executing `module`, with all host imports available.
+    pub fn build(module: &ModuleSource, config: &Config) -> Result<Self, Error> {
+        let mut builder = WasiExecutionContextBuilder::new(config)?;
+        builder.add_all()?;
+
+        Ok(Self(Arc::new(builder.build_source(module)?)))
+    }
+
+    async fn execute_impl(&self, req: Request<Body>) -> Result<Response<Body>, Error> {
+        // deislabs_http_v01 can only represent a subset of HTTP methods.
         let m = match *req.method() {
             http::Method::GET => Method::Get,
             http::Method::POST => Method::Post,
             http::Method::PUT => Method::Put,
             http::Method::DELETE => Method::Delete,
             http::Method::PATCH => Method::Patch,
-            _ => todo!(),
+            _ => {
+                log::info!(
+                    "Method {} is not supported by {}",
+                    req.method(),
+                    Self::INTERFACE
+                );
+                return Ok(Response::builder()
+                    .status(http::StatusCode::METHOD_NOT_ALLOWED)
+                    .header(http::header::ALLOW, "GET, POST, PUT, DELETE, PATCH")
+                    .body(Body::empty())?);
+            }
         };
         let u = req.uri().to_string();
 
-        let headers = Self::header_map_to_vec(req.headers())?;
+        // Parameters are serialized as `key=value`, like headers are as `name:value`.
+        let params: Vec<String> = request_params(&req)
+            .iter()
+            .map(|(k, v)| serialize_param(k, v))
+            .collect();
+        let params: Vec<&str> = params.iter().map(|s| &**s).collect();
+        let params = if params.is_empty() {
+            None
+        } else {
+            Some(&params[..])
+        };
+
+        // deislabs_http_v01 has no field for the connection, so it is
+        // described by synthetic `x-glass-*` headers.
+        let mut headers = Self::header_map_to_vec(req.headers())?;
+        if let Some(conn) = req.extensions().get::<ConnectionInfo>() {
+            for (name, value) in conn.headers(req.version()) {
+                headers.push(format!("{}:{}", name, value));
+            }
+        }
         let headers: Vec<&str> = headers.iter().map(|s| &**s).collect();
 
-        let (_, b) = req.into_parts();
-        let b = hyper::body::to_bytes(b).await?.to_vec();
-        let req = (m, u.as_str(), &headers[..], None, Some(&b[..]));
+        // The body is read before instantiating, so oversized requests are
+        // rejected without allocating an instance.
+        let (_, b) = to_bytes_limited(req).await?;
 
-        let (status, headers, body) = r.handler(&mut store, req)?;
+        let (mut store, instance) = self
+            .0
+            .prepare_exec(None)
+            .map_err(HttpError::instantiation)?;
+        let r = DeislabsHttpV01::new(&mut store, &instance, |host| {
+            host.runtime_data.as_mut().unwrap()
+        })
+        .map_err(HttpError::instantiation)?;
+
+        let req = (m, u.as_str(), &headers[..], params, Some(&b[..]));
+
+        let (status, headers, body) = self
+            .0
+            .call(&mut store, |store| Ok(r.handler(store, req)?))
+            .map_err(HttpError::guest_trap)?;
         log::info!("Result status code: {}", status);
+        let status = http::StatusCode::from_u16(status)
+            .map_err(|e| HttpError::invalid_response(e.into()))?;
         let mut hr = http::Response::builder().status(status);
-        Self::append_headers(hr.headers_mut().unwrap(), headers)?;
+        Self::append_headers(hr.headers_mut().unwrap(), headers)
+            .map_err(HttpError::invalid_response)?;
 
         let body = match body {
             Some(b) => Body::from(b),
             None => Body::empty(),
         };
 
-        Ok(hr.body(body)?)
+        hr.body(body)
+            .map_err(|e| HttpError::invalid_response(e.into()))
     }
 
     /// Generate a string vector from an HTTP header map.
+    ///
+    /// Headers with multiple values result in one entry per value. The
+    /// interface can only represent UTF-8 values, so any other value is
+    /// decoded lossily, with invalid sequences replaced by U+FFFD. Values
+    /// containing line breaks or NUL bytes, which cannot be passed to guests
+    /// reading them as lines or C strings, are rejected.
     fn header_map_to_vec(hm: &http::HeaderMap) -> Result<Vec<String>, Error> {
         let mut res = Vec::new();
-        for (name, value) in hm
-            .iter()
-            .map(|(name, value)| (name.as_str(), std::str::from_utf8(value.as_bytes())))
-        {
-            let value = value?;
-            anyhow::ensure!(
-                !name
-                    .chars()
-                    .any(|x| x.is_control() || "(),/:;<=>?@[\\]{}".contains(x)),
-                "Invalid header name"
-            );
-            anyhow::ensure!(
-                !value.chars().any(|x| x.is_control()),
-                "Invalid header value"
-            );
+        for (name, value) in hm.iter() {
+            if value
+                .as_bytes()
+                .iter()
+                .any(|b| matches!(b, b'\r' | b'\n' | 0))
+            {
+                return Err(HttpError::invalid_request(anyhow::anyhow!(
+                    "header {} cannot be represented in {}",
+                    name,
+                    Self::INTERFACE
+                )));
+            }
+            let value = String::from_utf8_lossy(value.as_bytes());
             res.push(format!("{}:{}", name, value));
         }
         Ok(res)
     }
 
     /// Append a header map string to a mutable http::HeaderMap.
+    /// Repeated header names are appended rather than replaced, so guests
+    /// can return multiple values, e.g. for `set-cookie`.
     fn append_headers(
         res_headers: &mut http::HeaderMap,
         source: Option<Vec<String>>,
@@ -101,11 +163,11 @@ impl Engine {
             Some(h) => {
                 for pair in h {
                     let mut parts = pair.splitn(2, ':');
-                    let k = parts.next().ok_or_else(|| {
-                        anyhow::format_err!("Invalid serialized header: [{}]", pair)
-                    })?;
-                    let v = parts.next().unwrap();
-                    res_headers.insert(
+                    let (k, v) = match (parts.next(), parts.next()) {
+                        (Some(k), Some(v)) => (k, v),
+                        _ => anyhow::bail!("Invalid serialized header: [{}]", pair),
+                    };
+                    res_headers.append(
                         http::header::HeaderName::from_str(k)?,
                         http::header::HeaderValue::from_str(v)?,
                     );
@@ -0,0 +1,246 @@
+use crate::{
+    connection::ConnectionInfo, error::HttpError, params::request_params, trigger::HttpEngine,
+};
+use anyhow::Error;
+use async_trait::async_trait;
+use deislabs_http_stream_host_v01::{DeislabsHttpStreamHostV01, StreamError};
+use deislabs_http_stream_v01::{
+    Connection, DeislabsHttpStreamV01, DeislabsHttpStreamV01Data, Method,
+};
+use glass_engine::{Config, ModuleSource, WasiExecutionContextBuilder};
+use hyper::{
+    body::{Bytes, HttpBody, Sender},
+    header::{HeaderName, HeaderValue},
+    Body, Request, Response, StatusCode,
+};
+use std::{sync::Arc, time::Instant};
+use tokio::{runtime::Handle, sync::oneshot};
+
+witx_bindgen_wasmtime::export!("crates/engine/test/http/deislabs_http_stream_v01.witx");
+witx_bindgen_wasmtime::import!("crates/engine/test/http/deislabs_http_stream_host_v01.witx");
+
+type WasiExecutionContext = glass_engine::WasiExecutionContext<StreamData>;
+
+/// Runtime data of an instance handling a streaming request.
+#[derive(Default)]
+pub struct StreamData {
+    bindings: DeislabsHttpStreamV01Data,
+    stream: Option<BodyStream>,
+}
+
+/// Host side of the request and response bodies of a streaming request.
+///
+/// The guest runs on a blocking thread, so the host functions block on the
+/// runtime until hyper has a chunk for them or can accept one.
+struct BodyStream {
+    handle: Handle,
+    body: Body,
+    /// Part of the last chunk received that the guest has not read yet.
+    pending: Bytes,
+    /// Where the response is sent once the guest starts it.
+    response: Option<oneshot::Sender<Response<Body>>>,
+    sender: Option<Sender>,
+}
+
+impl DeislabsHttpStreamHostV01 for BodyStream {
+    fn read_body(&mut self, max: u32) -> Result<Vec<u8>, StreamError> {
+        while self.pending.is_empty() {
+            match self.handle.block_on(self.body.data()) {
+                Some(Ok(chunk)) => self.pending = chunk,
+                Some(Err(e)) => {
+                    log::warn!("Cannot read request body: {}", e);
+                    return Err(StreamError::Closed);
+                }
+                None => return Ok(Vec::new()),
+            }
+        }
+
+        let len = self.pending.len().min(max as usize);
+        Ok(self.pending.split_to(len).to_vec())
+    }
+
+    fn start_response(
+        &mut self,
+        status: u16,
+        headers: Vec<(&str, &[u8])>,
+    ) -> Result<(), StreamError> {
+        if self.response.is_none() {
+            return Err(StreamError::AlreadyStarted);
+        }
+
+        let (sender, body) = Body::channel();
+        let mut res = Response::new(body);
+        *res.status_mut() =
+            StatusCode::from_u16(status).map_err(|_| StreamError::InvalidResponse)?;
+        for (name, value) in headers {
+            res.headers_mut().append(
+                HeaderName::from_bytes(name.as_bytes())
+                    .map_err(|_| StreamError::InvalidResponse)?,
+                HeaderValue::from_bytes(value).map_err(|_| StreamError::InvalidResponse)?,
+            );
+        }
+        log::info!("Result status code: {}", status);
+
+        let response = self.response.take().unwrap();
+        response.send(res).map_err(|_| StreamError::Closed)?;
+        self.sender = Some(sender);
+
+        Ok(())
+    }
+
+    fn write_body(&mut self, chunk: &[u8]) -> Result<(), StreamError> {
+        let sender = self.sender.as_mut().ok_or(StreamError::NotStarted)?;
+        self.handle
+            .block_on(sender.send_data(Bytes::copy_from_slice(chunk)))
+            .map_err(|_| StreamError::Closed)
+    }
+}
+
+/// Engine for modules implementing `deislabs_http_stream_v01`, which read
+/// the request body and write the response body in chunks, so neither has
+/// to fit in memory and the response starts before the guest returns.
+#[derive(Clone)]
+pub struct StreamingEngine(pub Arc<WasiExecutionContext>);
+
+#[async_trait]
+impl HttpEngine for StreamingEngine {
+    async fn execute(&self, req: Request<Body>) -> Result<Response<Body>, Error> {
+        let start = Instant::now();
+        let res = self.execute_impl(req).await?;
+        log::info!("Time to response head: {:#?}", start.elapsed());
+        Ok(res)
+    }
+}
+
+impl StreamingEngine {
+    /// Name of the interface implemented by modules this engine executes.
+    pub const INTERFACE: &'static str = "deislabs_http_stream_v01";
+    /// Functions modules must export to implement the interface.
+    pub const EXPORTS: &'static [&'static str] = &["handle_request_stream"];
+
+    /// Build an engine executing `module`, with all host imports available.
+    pub fn build(module: &ModuleSource, config: &Config) -> Result<Self, Error> {
+        let mut builder = WasiExecutionContextBuilder::new(config)?;
+        builder.add_all()?;
+        deislabs_http_stream_host_v01::add_to_linker(&mut builder.linker, |host| {
+            host.runtime_data.as_mut().unwrap().stream.as_mut().unwrap()
+        })?;
+
+        Ok(Self(Arc::new(builder.build_source(module)?)))
+    }
+
+    async fn execute_impl(&self, req: Request<Body>) -> Result<Response<Body>, Error> {
+        let (response, started) = oneshot::channel();
+        let (engine, handle) = (self.clone(), Handle::current());
+        let guest = tokio::task::spawn_blocking(move || engine.run_guest(req, response, handle));
+
+        // The guest keeps writing the body after the response is started.
+        if let Ok(res) = started.await {
+            return Ok(res);
+        }
+
+        guest.await??;
+        Err(HttpError::invalid_response(anyhow::anyhow!(
+            "the guest returned without starting a response"
+        )))
+    }
+
+    /// Run the guest to completion, on a thread that is allowed to block.
+    fn run_guest(
+        &self,
+        req: Request<Body>,
+        response: oneshot::Sender<Response<Body>>,
+        handle: Handle,
+    ) -> Result<(), Error> {
+        let method = req.method().clone();
+        let m = match method {
+            http::Method::GET => Method::Get,
+            http::Method::POST => Method::Post,
+            http::Method::PUT => Method::Put,
+            http::Method::DELETE => Method::Delete,
+            http::Method::PATCH => Method::Patch,
+            http::Method::HEAD => Method::Head,
+            http::Method::OPTIONS => Method::Options,
+            http::Method::CONNECT => Method::Connect,
+            http::Method::TRACE => Method::Trace,
+            ref other => Method::Other(other.as_str()),
+        };
+        let u = req.uri().to_string();
+
+        let params = request_params(&req);
+        let params: Vec<(&str, &str)> = params.iter().map(|(k, v)| (&**k, &**v)).collect();
+        let params = if params.is_empty() {
+            None
+        } else {
+            Some(&params[..])
+        };
+
+        let version = format!("{:?}", req.version());
+        let conn = req.extensions().get::<ConnectionInfo>().map(|c| {
+            (
+                c.remote_addr.to_string(),
+                c.peer_addr.to_string(),
+                c.local_addr.to_string(),
+                c.scheme.clone(),
+            )
+        });
+        let conn = conn
+            .as_ref()
+            .map(|(remote, peer, local, scheme)| Connection {
+                remote_addr: remote,
+                peer_addr: peer,
+                local_addr: local,
+                scheme,
+                http_version: &version,
+            });
+
+        let (parts, body) = req.into_parts();
+        let headers: Vec<(&str, &[u8])> = parts
+            .headers
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_bytes()))
+            .collect();
+
+        let data = StreamData {
+            bindings: DeislabsHttpStreamV01Data::default(),
+            stream: Some(BodyStream {
+                handle,
+                body,
+                pending: Bytes::new(),
+                response: Some(response),
+                sender: None,
+            }),
+        };
+        let (mut store, instance) = self
+            .0
+            .prepare_exec(Some(data))
+            .map_err(HttpError::instantiation)?;
+        let r = DeislabsHttpStreamV01::new(&mut store, &instance, |host| {
+            &mut host.runtime_data.as_mut().unwrap().bindings
+        })
+        .map_err(HttpError::instantiation)?;
+
+        let req = (m, u.as_str(), &headers[..], params, conn);
+        let res = self
+            .0
+            .call(&mut store, |store| Ok(r.handle_request_stream(store, req)?));
+
+        // Dropping the stream ends the response body, or reports to
+        // `execute_impl` that the response was never started.
+        let stream = store
+            .data_mut()
+            .runtime_data
+            .as_mut()
+            .and_then(|data| data.stream.take());
+        match (res, stream.and_then(|s| s.sender)) {
+            (Err(e), Some(sender)) => {
+                // The status was already sent, so the client can only learn
+                // about the failure from the body being cut short.
+                log::error!("Guest failed while streaming the response: {:#}", e);
+                sender.abort();
+                Ok(())
+            }
+            (res, _) => res.map_err(HttpError::guest_trap),
+        }
+    }
+}
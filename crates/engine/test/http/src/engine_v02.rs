@@ -0,0 +1,144 @@
+use crate::{
+    body::to_bytes_limited, connection::ConnectionInfo, error::HttpError, params::request_params,
+    trigger::HttpEngine,
+};
+use anyhow::Error;
+use async_trait::async_trait;
+use deislabs_http_v02::{Connection, DeislabsHttpV02, DeislabsHttpV02Data, Method};
+use glass_engine::{Config, ModuleSource, WasiExecutionContextBuilder};
+use hyper::{Body, Request, Response};
+use std::{sync::Arc, time::Instant};
+
+witx_bindgen_wasmtime::export!("crates/engine/test/http/deislabs_http_v02.witx");
+
+type WasiExecutionContext = glass_engine::WasiExecutionContext<DeislabsHttpV02Data>;
+
+/// Engine for modules implementing `deislabs_http_v02`, which can
+/// represent every HTTP method.
+#[derive(Clone)]
+pub struct EngineV02(pub Arc<WasiExecutionContext>);
+
+#[async_trait]
+impl HttpEngine for EngineV02 {
+    async fn execute(&self, req: Request<Body>) -> Result<Response<Body>, Error> {
+        let start = Instant::now();
+        let res = self.execute_impl(req).await?;
+        log::info!("Total request execution time: {:#?}", start.elapsed());
+        Ok(res)
+    }
+}
+
+impl EngineV02 {
+    /// Name of the interface implemented by modules this engine executes.
+    pub const INTERFACE: &'static str = "deislabs_http_v02";
+    /// Functions modules must export to implement the interface.
+    pub const EXPORTS: &'static [&'static str] = &["handle_request"];
+
+    /// Build an engine executing `module`, with all host imports available.
+    pub fn build(module: &ModuleSource, config: &Config) -> Result<Self, Error> {
+        let mut builder = WasiExecutionContextBuilder::new(config)?;
+        builder.add_all()?;
+
+        Ok(Self(Arc::new(builder.build_source(module)?)))
+    }
+
+    async fn execute_impl(&self, req: Request<Body>) -> Result<Response<Body>, Error> {
+        let method = req.method().clone();
+        let m = match method {
+            http::Method::GET => Method::Get,
+            http::Method::POST => Method::Post,
+            http::Method::PUT => Method::Put,
+            http::Method::DELETE => Method::Delete,
+            http::Method::PATCH => Method::Patch,
+            http::Method::HEAD => Method::Head,
+            http::Method::OPTIONS => Method::Options,
+            http::Method::CONNECT => Method::Connect,
+            http::Method::TRACE => Method::Trace,
+            ref other => Method::Other(other.as_str()),
+        };
+        let u = req.uri().to_string();
+
+        let params = request_params(&req);
+        let params: Vec<(&str, &str)> = params.iter().map(|(k, v)| (&**k, &**v)).collect();
+        let params = if params.is_empty() {
+            None
+        } else {
+            Some(&params[..])
+        };
+
+        let version = format!("{:?}", req.version());
+        let conn = req.extensions().get::<ConnectionInfo>().map(|c| {
+            (
+                c.remote_addr.to_string(),
+                c.peer_addr.to_string(),
+                c.local_addr.to_string(),
+                c.scheme.clone(),
+            )
+        });
+        let conn = conn
+            .as_ref()
+            .map(|(remote, peer, local, scheme)| Connection {
+                remote_addr: remote,
+                peer_addr: peer,
+                local_addr: local,
+                scheme,
+                http_version: &version,
+            });
+
+        let (parts, b) = to_bytes_limited(req).await?;
+        let headers = Self::header_pairs(&parts.headers);
+
+        let (mut store, instance) = self
+            .0
+            .prepare_exec(None)
+            .map_err(HttpError::instantiation)?;
+        let r = DeislabsHttpV02::new(&mut store, &instance, |host| {
+            host.runtime_data.as_mut().unwrap()
+        })
+        .map_err(HttpError::instantiation)?;
+
+        let req = (m, u.as_str(), &headers[..], params, Some(&b[..]), conn);
+
+        let (status, headers, body) = self
+            .0
+            .call(&mut store, |store| Ok(r.handle_request(store, req)?))
+            .map_err(HttpError::guest_trap)?;
+        log::info!("Result status code: {}", status);
+        let status = http::StatusCode::from_u16(status)
+            .map_err(|e| HttpError::invalid_response(e.into()))?;
+        let mut hr = http::Response::builder().status(status);
+        Self::append_headers(hr.headers_mut().unwrap(), headers)
+            .map_err(HttpError::invalid_response)?;
+
+        let body = match body {
+            Some(b) => Body::from(b),
+            None => Body::empty(),
+        };
+
+        hr.body(body)
+            .map_err(|e| HttpError::invalid_response(e.into()))
+    }
+
+    /// Get the name and raw value of every header, with one entry per value.
+    fn header_pairs(hm: &http::HeaderMap) -> Vec<(&str, &[u8])> {
+        hm.iter()
+            .map(|(name, value)| (name.as_str(), value.as_bytes()))
+            .collect()
+    }
+
+    /// Append the headers returned by the guest, preserving their order and
+    /// any repeated names.
+    fn append_headers(
+        res_headers: &mut http::HeaderMap,
+        source: Option<Vec<(String, Vec<u8>)>>,
+    ) -> Result<(), Error> {
+        for (name, value) in source.unwrap_or_default() {
+            res_headers.append(
+                http::header::HeaderName::from_bytes(name.as_bytes())?,
+                http::header::HeaderValue::from_bytes(&value)?,
+            );
+        }
+
+        Ok(())
+    }
+}
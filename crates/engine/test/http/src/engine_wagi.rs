@@ -0,0 +1,217 @@
+use crate::{
+    body::to_bytes_limited, connection::ConnectionInfo, error::HttpError, route::RoutePattern,
+    trigger::HttpEngine,
+};
+use anyhow::Error;
+use async_trait::async_trait;
+use glass_engine::{Config, Invocation, ModuleSource, WasiExecutionContextBuilder};
+use hyper::{
+    header::{self, HeaderName, HeaderValue},
+    Body, Request, Response, StatusCode,
+};
+use std::{sync::Arc, time::Instant};
+use wasi_common::pipe::{ReadPipe, WritePipe};
+
+type WasiExecutionContext = glass_engine::WasiExecutionContext<()>;
+
+/// Engine for Wagi modules, which are WASI commands that handle a request
+/// the way a CGI script does: they get the request metadata from environment
+/// variables and its body from stdin, and write the response to stdout.
+#[derive(Clone)]
+pub struct WagiEngine(pub Arc<WasiExecutionContext>);
+
+#[async_trait]
+impl HttpEngine for WagiEngine {
+    async fn execute(&self, req: Request<Body>) -> Result<Response<Body>, Error> {
+        let start = Instant::now();
+        let res = self.execute_impl(req).await?;
+        log::info!("Total request execution time: {:#?}", start.elapsed());
+        Ok(res)
+    }
+}
+
+impl WagiEngine {
+    /// Name of the interface implemented by modules this engine executes.
+    pub const INTERFACE: &'static str = "wagi";
+    /// Functions modules must export to implement the interface.
+    pub const EXPORTS: &'static [&'static str] = &["_start"];
+
+    /// Build an engine executing `module`, with all host imports available.
+    pub fn build(module: &ModuleSource, config: &Config) -> Result<Self, Error> {
+        let mut builder = WasiExecutionContextBuilder::new(config)?;
+        builder.add_all()?;
+
+        Ok(Self(Arc::new(builder.build_source(module)?)))
+    }
+
+    async fn execute_impl(&self, req: Request<Body>) -> Result<Response<Body>, Error> {
+        let envs = Self::cgi_env(&req);
+        let (_, b) = to_bytes_limited(req).await?;
+
+        let stdout = WritePipe::new_in_memory();
+        let invocation = Invocation {
+            stdin: Some(Box::new(ReadPipe::from(b))),
+            stdout: Some(Box::new(stdout.clone())),
+            envs,
+        };
+
+        {
+            let (mut store, instance) = self
+                .0
+                .prepare_exec_with(None, invocation)
+                .map_err(HttpError::instantiation)?;
+            let start = instance
+                .get_typed_func::<(), (), _>(&mut store, "_start")
+                .map_err(HttpError::instantiation)?;
+
+            self.0
+                .call(&mut store, |store| match start.call(store, ()) {
+                    Ok(()) => Ok(()),
+                    // Commands calling `proc_exit(0)` completed successfully.
+                    Err(trap) if trap.i32_exit_status() == Some(0) => Ok(()),
+                    Err(trap) => Err(trap.into()),
+                })
+                .map_err(HttpError::guest_trap)?;
+        }
+
+        // The store, and the copy of the pipe it held, are dropped by now.
+        let output = stdout
+            .try_into_inner()
+            .map_err(|_| anyhow::anyhow!("guest stdout is still in use"))?
+            .into_inner();
+
+        parse_cgi_response(&output).map_err(HttpError::invalid_response)
+    }
+
+    /// Get the CGI environment variables describing a request.
+    fn cgi_env(req: &Request<Body>) -> Vec<(String, String)> {
+        let uri = req.uri();
+        let (script_name, path_info) = match req.extensions().get::<RoutePattern>() {
+            Some(pattern) => pattern.split_path(uri.path()),
+            None => (String::new(), uri.path().to_string()),
+        };
+        let host = req
+            .headers()
+            .get(header::HOST)
+            .and_then(|h| h.to_str().ok())
+            .or_else(|| uri.host())
+            .unwrap_or("localhost");
+        let (server_name, server_port) = match host.rsplit_once(':') {
+            Some((name, port)) if port.parse::<u16>().is_ok() => (name, port.to_string()),
+            _ => (host, uri.port_u16().unwrap_or(80).to_string()),
+        };
+
+        let mut envs = vec![
+            ("GATEWAY_INTERFACE", "CGI/1.1".to_string()),
+            ("SERVER_SOFTWARE", "glass".to_string()),
+            ("SERVER_PROTOCOL", format!("{:?}", req.version())),
+            ("SERVER_NAME", server_name.to_string()),
+            ("SERVER_PORT", server_port),
+            ("REQUEST_METHOD", req.method().to_string()),
+            ("SCRIPT_NAME", script_name),
+            ("PATH_INFO", path_info.clone()),
+            ("PATH_TRANSLATED", path_info),
+            ("QUERY_STRING", uri.query().unwrap_or_default().to_string()),
+            ("X_RAW_PATH_INFO", uri.path().to_string()),
+            ("X_FULL_URL", uri.to_string()),
+            ("AUTH_TYPE", String::new()),
+            ("REMOTE_USER", String::new()),
+        ];
+        if let Some(pattern) = req.extensions().get::<RoutePattern>() {
+            envs.push(("X_MATCHED_ROUTE", pattern.to_string()));
+        }
+        if let Some(conn) = req.extensions().get::<ConnectionInfo>() {
+            envs.push(("REMOTE_ADDR", conn.remote_addr.to_string()));
+            envs.push(("REMOTE_HOST", conn.remote_addr.to_string()));
+        }
+
+        let mut envs: Vec<(String, String)> =
+            envs.into_iter().map(|(k, v)| (k.to_string(), v)).collect();
+        for (name, value) in req.headers() {
+            let value = String::from_utf8_lossy(value.as_bytes()).into_owned();
+            let name = match *name {
+                header::CONTENT_TYPE => "CONTENT_TYPE".to_string(),
+                header::CONTENT_LENGTH => "CONTENT_LENGTH".to_string(),
+                // Credentials are not passed to scripts, like in most CGI servers.
+                header::AUTHORIZATION => continue,
+                _ => format!("HTTP_{}", name.as_str().to_uppercase().replace('-', "_")),
+            };
+            envs.push((name, value));
+        }
+
+        envs
+    }
+}
+
+/// Parse the output of a CGI script into a response.
+///
+/// The output starts with headers and a blank line. The status is taken from
+/// the `Status` header, and defaults to 302 Found if there is a `Location`
+/// header, or to 200 OK otherwise.
+pub fn parse_cgi_response(output: &[u8]) -> Result<Response<Body>, Error> {
+    let (head, body) = split_head(output)
+        .ok_or_else(|| anyhow::anyhow!("the CGI response has no header section"))?;
+
+    let mut res = Response::new(Body::empty());
+    let mut status = None;
+    for line in head.split(|b| *b == b'\n') {
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        if line.is_empty() {
+            continue;
+        }
+
+        let colon = line
+            .iter()
+            .position(|b| *b == b':')
+            .ok_or_else(|| anyhow::anyhow!("invalid CGI header line"))?;
+        let name = HeaderName::from_bytes(&line[..colon])?;
+        let value = trim(&line[colon + 1..]);
+
+        if name.as_str() == "status" {
+            // The reason phrase after the code is ignored.
+            let code = value.split(|b| *b == b' ').next().unwrap_or_default();
+            status = Some(StatusCode::from_bytes(code)?);
+        } else {
+            res.headers_mut()
+                .append(name, HeaderValue::from_bytes(value)?);
+        }
+    }
+
+    *res.status_mut() = match status {
+        Some(status) => status,
+        None if res.headers().contains_key(header::LOCATION) => StatusCode::FOUND,
+        None => StatusCode::OK,
+    };
+    *res.body_mut() = Body::from(body.to_vec());
+
+    Ok(res)
+}
+
+/// Split the output at the first blank line, which can use either line ending.
+fn split_head(output: &[u8]) -> Option<(&[u8], &[u8])> {
+    (0..output.len()).find_map(|i| {
+        let rest = &output[i..];
+        if rest.starts_with(b"\r\n\r\n") {
+            Some((&output[..i], &output[i + 4..]))
+        } else if rest.starts_with(b"\n\n") {
+            Some((&output[..i], &output[i + 2..]))
+        } else if rest.starts_with(b"\n\r\n") {
+            Some((&output[..i], &output[i + 3..]))
+        } else {
+            None
+        }
+    })
+}
+
+fn trim(value: &[u8]) -> &[u8] {
+    let start = value
+        .iter()
+        .position(|b| !b.is_ascii_whitespace())
+        .unwrap_or(value.len());
+    let end = value
+        .iter()
+        .rposition(|b| !b.is_ascii_whitespace())
+        .map_or(start, |i| i + 1);
+
+    &value[start..end]
+}
@@ -0,0 +1,167 @@
+use anyhow::Error;
+use glass_engine::ExecutionError;
+use hyper::{
+    header::{self, HeaderValue},
+    Body, Response, StatusCode,
+};
+use std::fmt;
+
+/// Header carrying the id correlating an error response with the server logs.
+pub const CORRELATION_ID_HEADER: &str = "x-correlation-id";
+
+/// Reasons an HTTP engine can fail to produce a response from the guest.
+#[derive(Debug)]
+pub enum HttpError {
+    /// The module could not be instantiated, or does not export the interface.
+    Instantiation(Error),
+    /// The guest trapped while handling the request.
+    GuestTrap(Error),
+    /// The guest returned a response that is not valid HTTP.
+    InvalidResponse(Error),
+    /// The request cannot be represented in the interface of the guest.
+    InvalidRequest(Error),
+    /// The request body is larger than the engine accepts.
+    PayloadTooLarge(Error),
+    /// The guest ran into one of the execution limits set on the engine.
+    LimitsExceeded(Error),
+    /// The guest did not complete before its deadline.
+    Timeout(Error),
+}
+
+impl HttpError {
+    /// Classify an error raised while instantiating the module.
+    pub fn instantiation(err: Error) -> Error {
+        Self::classify(err, HttpError::Instantiation)
+    }
+
+    /// Classify an error raised while the guest handled the request.
+    pub fn guest_trap(err: Error) -> Error {
+        Self::classify(err, HttpError::GuestTrap)
+    }
+
+    /// Wrap an error raised while converting the request for the guest.
+    pub fn invalid_request(err: Error) -> Error {
+        HttpError::InvalidRequest(err).into()
+    }
+
+    /// Wrap an error raised while converting the guest's response.
+    pub fn invalid_response(err: Error) -> Error {
+        HttpError::InvalidResponse(err).into()
+    }
+
+    /// Status code of the response sent to the client.
+    pub fn status(&self) -> StatusCode {
+        match self {
+            HttpError::Instantiation(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            HttpError::GuestTrap(_) | HttpError::InvalidResponse(_) => StatusCode::BAD_GATEWAY,
+            HttpError::InvalidRequest(_) => StatusCode::BAD_REQUEST,
+            HttpError::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            HttpError::LimitsExceeded(_) => StatusCode::SERVICE_UNAVAILABLE,
+            HttpError::Timeout(_) => StatusCode::GATEWAY_TIMEOUT,
+        }
+    }
+
+    /// Description of the error that is safe to send to clients.
+    fn detail(&self) -> &'static str {
+        match self {
+            HttpError::Instantiation(_) => "The handler could not be started.",
+            HttpError::GuestTrap(_) => "The handler failed while processing the request.",
+            HttpError::InvalidResponse(_) => "The handler returned an invalid response.",
+            HttpError::InvalidRequest(_) => "The request cannot be passed to the handler.",
+            HttpError::PayloadTooLarge(_) => "The request body is too large.",
+            HttpError::LimitsExceeded(_) => "The handler exceeded its resource limits.",
+            HttpError::Timeout(_) => "The handler did not respond in time.",
+        }
+    }
+
+    fn inner(&self) -> &Error {
+        match self {
+            HttpError::Instantiation(e)
+            | HttpError::GuestTrap(e)
+            | HttpError::InvalidResponse(e)
+            | HttpError::InvalidRequest(e)
+            | HttpError::PayloadTooLarge(e)
+            | HttpError::LimitsExceeded(e)
+            | HttpError::Timeout(e) => e,
+        }
+    }
+
+    /// Use `kind` for the error, unless the guest ran into its execution limits.
+    fn classify(err: Error, kind: fn(Error) -> HttpError) -> Error {
+        let kind = match err.downcast_ref::<ExecutionError>() {
+            Some(ExecutionError::Timeout { .. }) => HttpError::Timeout,
+            Some(_) => HttpError::LimitsExceeded,
+            None => kind,
+        };
+
+        kind(err).into()
+    }
+}
+
+impl fmt::Display for HttpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self {
+            HttpError::Instantiation(_) => "instantiation failed",
+            HttpError::GuestTrap(_) => "guest trapped",
+            HttpError::InvalidResponse(_) => "invalid guest response",
+            HttpError::InvalidRequest(_) => "invalid request",
+            HttpError::PayloadTooLarge(_) => "payload too large",
+            HttpError::LimitsExceeded(_) => "limits exceeded",
+            HttpError::Timeout(_) => "timed out",
+        };
+        write!(f, "{}: {:#}", kind, self.inner())
+    }
+}
+
+impl std::error::Error for HttpError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(self.inner().as_ref())
+    }
+}
+
+/// Build the response for a request that failed with `err`.
+///
+/// The error is logged along with a correlation id that is returned to the
+/// client, but none of its details are. If `problem_details` is set, the
+/// response has an RFC 7807 JSON body.
+pub fn error_response(err: &Error, problem_details: bool) -> Response<Body> {
+    let correlation_id = uuid::Uuid::new_v4().to_string();
+    let (status, detail) = match err.downcast_ref::<HttpError>() {
+        Some(e) => (e.status(), e.detail()),
+        None => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "The request could not be processed.",
+        ),
+    };
+    log::error!(
+        "[{}] Request failed with {}: {:#}",
+        correlation_id,
+        status,
+        err
+    );
+
+    let mut res = if problem_details {
+        let body = serde_json::json!({
+            "type": "about:blank",
+            "title": status.canonical_reason().unwrap_or_default(),
+            "status": status.as_u16(),
+            "detail": detail,
+            "correlationId": correlation_id,
+        });
+        let mut res = Response::new(Body::from(body.to_string()));
+        res.headers_mut().insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/problem+json"),
+        );
+        res
+    } else {
+        Response::new(Body::empty())
+    };
+
+    *res.status_mut() = status;
+    if let Ok(id) = HeaderValue::from_str(&correlation_id) {
+        res.headers_mut().insert(CORRELATION_ID_HEADER, id);
+    }
+
+    res
+}
@@ -0,0 +1,31 @@
+use crate::{BoxedEngine, Engine, EngineV02, StreamingEngine, WagiEngine};
+use glass_engine::{Interface, InterfaceRegistry};
+
+/// The HTTP interfaces supported by glass.
+pub fn registry() -> InterfaceRegistry<BoxedEngine> {
+    let mut registry = InterfaceRegistry::new();
+    registry.register(Interface {
+        name: Engine::INTERFACE,
+        exports: Engine::EXPORTS,
+        build: |module, config| Ok(BoxedEngine::new(Engine::build(module, config)?)),
+    });
+    registry.register(Interface {
+        name: EngineV02::INTERFACE,
+        exports: EngineV02::EXPORTS,
+        build: |module, config| Ok(BoxedEngine::new(EngineV02::build(module, config)?)),
+    });
+    registry.register(Interface {
+        name: StreamingEngine::INTERFACE,
+        exports: StreamingEngine::EXPORTS,
+        build: |module, config| Ok(BoxedEngine::new(StreamingEngine::build(module, config)?)),
+    });
+    // Any WASI command exports `_start`, so Wagi is registered last for
+    // `auto` to prefer the more specific interfaces.
+    registry.register(Interface {
+        name: WagiEngine::INTERFACE,
+        exports: WagiEngine::EXPORTS,
+        build: |module, config| Ok(BoxedEngine::new(WagiEngine::build(module, config)?)),
+    });
+
+    registry
+}
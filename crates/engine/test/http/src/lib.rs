@@ -1,5 +1,25 @@
+pub mod body;
+pub mod connection;
 pub mod engine;
+pub mod engine_stream;
+pub mod engine_v02;
+pub mod engine_wagi;
+pub mod error;
+pub mod interfaces;
+pub mod params;
+pub mod route;
+pub mod router;
+pub mod tls;
 pub mod trigger;
 
+pub use body::BodyLimit;
+pub use connection::ConnectionInfo;
 pub use engine::Engine;
-pub use trigger::{HttpEngine, Trigger};
+pub use engine_stream::StreamingEngine;
+pub use engine_v02::EngineV02;
+pub use engine_wagi::WagiEngine;
+pub use error::HttpError;
+pub use route::{RouteParams, RoutePattern};
+pub use router::Router;
+pub use tls::TlsConfig;
+pub use trigger::{BoxedEngine, DynHttpEngine, HttpEngine, Trigger};
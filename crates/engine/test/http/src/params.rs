@@ -0,0 +1,36 @@
+use crate::route::RouteParams;
+use hyper::{Body, Request};
+
+/// Parse the query string of a request into percent-decoded key/value
+/// pairs, preserving repeated keys and their order.
+pub fn query_params(req: &Request<Body>) -> Vec<(String, String)> {
+    match req.uri().query() {
+        Some(query) => form_urlencoded::parse(query.as_bytes())
+            .into_owned()
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
+/// Get the route parameters of a request followed by its query parameters.
+pub fn request_params(req: &Request<Body>) -> Vec<(String, String)> {
+    let mut params = req
+        .extensions()
+        .get::<RouteParams>()
+        .map(|p| p.0.clone())
+        .unwrap_or_default();
+    params.extend(query_params(req));
+
+    params
+}
+
+/// Serialize a parameter as `key=value` for interfaces representing them as
+/// strings.
+///
+/// Any `%` and `=` in the key are percent-encoded, so the first `=` always
+/// separates the key from the value and `?a%3Db=c` does not collide with
+/// `?a=b%3Dc`. The value is left decoded.
+pub fn serialize_param(key: &str, value: &str) -> String {
+    let key = key.replace('%', "%25").replace('=', "%3D");
+    format!("{}={}", key, value)
+}
@@ -0,0 +1,126 @@
+use anyhow::Error;
+use percent_encoding::percent_decode_str;
+use std::{fmt, str::FromStr};
+
+/// Named captures of the route pattern that matched the request path.
+///
+/// The router adds them to the request extensions, and engines pass them
+/// to the guest along with the query parameters.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RouteParams(pub Vec<(String, String)>);
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Segment {
+    Literal(String),
+    Capture(String),
+    Wildcard,
+}
+
+/// A route pattern matched segment by segment against the request path.
+///
+/// - `/about` matches exactly that path.
+/// - `/users/:id` captures the second segment as the `id` parameter.
+/// - `/files/*/raw` matches any single segment in place of `*`.
+/// - `/api/*` matches `/api` and any path under it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RoutePattern {
+    pattern: String,
+    segments: Vec<Segment>,
+    prefix: bool,
+}
+
+impl RoutePattern {
+    /// Match `path` against the pattern, returning the percent-decoded
+    /// named captures if it matches.
+    pub fn captures(&self, path: &str) -> Option<RouteParams> {
+        let segments: Vec<&str> = split(path).collect();
+        if segments.len() < self.segments.len()
+            || (!self.prefix && segments.len() != self.segments.len())
+        {
+            return None;
+        }
+
+        let mut params = Vec::new();
+        for (pattern, segment) in self.segments.iter().zip(segments) {
+            match pattern {
+                Segment::Literal(literal) if literal == segment => {}
+                Segment::Literal(_) => return None,
+                Segment::Wildcard => {}
+                Segment::Capture(name) => params.push((
+                    name.clone(),
+                    percent_decode_str(segment).decode_utf8_lossy().into_owned(),
+                )),
+            }
+        }
+
+        Some(RouteParams(params))
+    }
+
+    /// Split a path matching the pattern into the part matched by the
+    /// pattern and the rest, like CGI's `SCRIPT_NAME` and `PATH_INFO`.
+    pub fn split_path(&self, path: &str) -> (String, String) {
+        let segments: Vec<&str> = split(path).collect();
+        let (matched, rest) = segments.split_at(self.segments.len().min(segments.len()));
+        let join = |segments: &[&str]| {
+            segments
+                .iter()
+                .map(|s| format!("/{}", s))
+                .collect::<String>()
+        };
+
+        (join(matched), join(rest))
+    }
+
+    /// Rank of the pattern when several match the same path: longer
+    /// patterns first, then those with more literal segments, then
+    /// exact patterns before prefixes.
+    pub(crate) fn specificity(&self) -> (usize, usize, bool) {
+        let literals = self
+            .segments
+            .iter()
+            .filter(|s| matches!(s, Segment::Literal(_)))
+            .count();
+
+        (self.segments.len(), literals, !self.prefix)
+    }
+}
+
+impl FromStr for RoutePattern {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        anyhow::ensure!(s.starts_with('/'), "route '{}' must start with '/'", s);
+
+        let mut segments = split(s)
+            .map(|segment| match segment {
+                "*" => Ok(Segment::Wildcard),
+                _ => match segment.strip_prefix(':') {
+                    Some("") => anyhow::bail!("route '{}' has an unnamed capture", s),
+                    Some(name) => Ok(Segment::Capture(name.to_string())),
+                    None => Ok(Segment::Literal(segment.to_string())),
+                },
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let prefix = s.ends_with("/*");
+        if prefix {
+            segments.pop();
+        }
+
+        Ok(Self {
+            pattern: s.to_string(),
+            segments,
+            prefix,
+        })
+    }
+}
+
+impl fmt::Display for RoutePattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.pattern)
+    }
+}
+
+fn split(path: &str) -> impl Iterator<Item = &str> {
+    path.split('/').filter(|s| !s.is_empty())
+}
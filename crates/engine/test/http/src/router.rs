@@ -0,0 +1,76 @@
+use crate::{route::RoutePattern, trigger::HttpEngine};
+use anyhow::Error;
+use async_trait::async_trait;
+use hyper::{Body, Request, Response, StatusCode};
+use std::sync::Arc;
+
+/// Dispatches each request to the engine of the most specific route
+/// matching its path, or to the not found engine if none does.
+///
+/// The matched `RoutePattern` and its `RouteParams` are added to the
+/// request extensions.
+#[derive(Clone)]
+pub struct Router<E> {
+    routes: Arc<Vec<(RoutePattern, E)>>,
+    not_found: Option<E>,
+}
+
+impl<E: HttpEngine> Router<E> {
+    pub fn new() -> Self {
+        Self {
+            routes: Arc::new(Vec::new()),
+            not_found: None,
+        }
+    }
+
+    /// Add a route. Among equally specific routes, the first added wins.
+    pub fn route(mut self, pattern: RoutePattern, engine: E) -> Self {
+        let routes = Arc::make_mut(&mut self.routes);
+        let pos = routes
+            .iter()
+            .position(|(p, _)| p.specificity() < pattern.specificity())
+            .unwrap_or_else(|| routes.len());
+        routes.insert(pos, (pattern, engine));
+
+        self
+    }
+
+    /// Set the engine for requests that match no route. Without one,
+    /// they are answered with an empty 404 Not Found.
+    pub fn not_found(mut self, engine: E) -> Self {
+        self.not_found = Some(engine);
+        self
+    }
+
+    /// Get the patterns of all routes, most specific first.
+    pub fn patterns(&self) -> Vec<&RoutePattern> {
+        self.routes.iter().map(|(p, _)| p).collect()
+    }
+}
+
+impl<E: HttpEngine> Default for Router<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl<E: HttpEngine> HttpEngine for Router<E> {
+    async fn execute(&self, mut req: Request<Body>) -> Result<Response<Body>, Error> {
+        for (pattern, engine) in self.routes.iter() {
+            if let Some(params) = pattern.captures(req.uri().path()) {
+                log::debug!("Routing {} to {}", req.uri().path(), pattern);
+                req.extensions_mut().insert(params);
+                req.extensions_mut().insert(pattern.clone());
+                return engine.execute(req).await;
+            }
+        }
+
+        match &self.not_found {
+            Some(engine) => engine.execute(req).await,
+            None => Ok(Response::builder()
+                .status(StatusCode::NOT_FOUND)
+                .body(Body::empty())?),
+        }
+    }
+}
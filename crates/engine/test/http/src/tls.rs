@@ -0,0 +1,94 @@
+use anyhow::{Context, Error};
+use glass_engine::Reloadable;
+use rustls::{
+    server::AllowAnyAuthenticatedClient, Certificate, PrivateKey, RootCertStore, ServerConfig,
+};
+use rustls_pemfile::Item;
+use std::{fs::File, io::BufReader, path::PathBuf, sync::Arc, time::Duration};
+
+/// How often the certificate files are checked for changes.
+const WATCH_INTERVAL: Duration = Duration::from_secs(10);
+
+/// PEM files the trigger serves HTTPS with.
+#[derive(Clone, Debug)]
+pub struct TlsConfig {
+    /// Certificate chain, leaf first.
+    pub cert: PathBuf,
+    pub key: PathBuf,
+    /// When set, clients must present a certificate signed by one of
+    /// the CAs in this bundle.
+    pub client_ca: Option<PathBuf>,
+}
+
+impl TlsConfig {
+    /// Load the rustls configuration from the PEM files.
+    pub fn server_config(&self) -> Result<Arc<ServerConfig>, Error> {
+        let certs = load_certs(&self.cert)?;
+        let key = load_key(&self.key)?;
+
+        let builder = ServerConfig::builder().with_safe_defaults();
+        let builder = match &self.client_ca {
+            Some(path) => {
+                let mut roots = RootCertStore::empty();
+                for cert in load_certs(path)? {
+                    roots
+                        .add(&cert)
+                        .with_context(|| format!("invalid CA certificate in {}", path.display()))?;
+                }
+                builder.with_client_cert_verifier(AllowAnyAuthenticatedClient::new(roots))
+            }
+            None => builder.with_no_client_auth(),
+        };
+
+        let mut config = builder
+            .with_single_cert(certs, key)
+            .context("invalid TLS certificate or key")?;
+        config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+
+        Ok(Arc::new(config))
+    }
+
+    /// Load the rustls configuration, and load it again in the background
+    /// whenever one of the PEM files changes.
+    pub fn watch(&self) -> Result<Reloadable<Arc<ServerConfig>>, Error> {
+        let tls = self.clone();
+        let config = Reloadable::new(move || tls.server_config())?;
+
+        config.watch(self.cert.clone(), WATCH_INTERVAL);
+        config.watch(self.key.clone(), WATCH_INTERVAL);
+        if let Some(path) = &self.client_ca {
+            config.watch(path.clone(), WATCH_INTERVAL);
+        }
+
+        Ok(config)
+    }
+}
+
+fn load_certs(path: &PathBuf) -> Result<Vec<Certificate>, Error> {
+    let mut reader = BufReader::new(
+        File::open(path).with_context(|| format!("cannot open {}", path.display()))?,
+    );
+    let certs: Vec<Certificate> = rustls_pemfile::certs(&mut reader)?
+        .into_iter()
+        .map(Certificate)
+        .collect();
+    anyhow::ensure!(!certs.is_empty(), "no certificate in {}", path.display());
+
+    Ok(certs)
+}
+
+fn load_key(path: &PathBuf) -> Result<PrivateKey, Error> {
+    let mut reader = BufReader::new(
+        File::open(path).with_context(|| format!("cannot open {}", path.display()))?,
+    );
+    for item in rustls_pemfile::read_all(&mut reader)? {
+        match item {
+            Item::RSAKey(key) | Item::PKCS8Key(key) | Item::ECKey(key) => {
+                return Ok(PrivateKey(key))
+            }
+            _ => {}
+        }
+    }
+
+    anyhow::bail!("no private key in {}", path.display())
+}
@@ -1,36 +1,222 @@
+use crate::{
+    body::BodyLimit,
+    connection::{strip_synthetic_headers, ConnectionInfo},
+    error::{error_response, HttpError},
+    tls::TlsConfig,
+};
 use anyhow::Error;
 use async_trait::async_trait;
+use glass_engine::Reloadable;
 use hyper::{
-    server::conn::AddrStream,
+    server::conn::{AddrStream, Http},
     service::{make_service_fn, service_fn},
     Body, Request, Response, Server,
 };
-use std::net::SocketAddr;
+use ipnet::IpNet;
+use std::{convert::Infallible, net::SocketAddr, sync::Arc, time::Duration};
+use tokio::net::TcpListener;
+use tokio_rustls::TlsAcceptor;
+
+/// Time clients have to complete the TLS handshake before the connection
+/// is closed.
+const TLS_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+/// Pause after failing to accept a connection, so running out of file
+/// descriptors does not turn the accept loop into a busy loop.
+const ACCEPT_ERROR_DELAY: Duration = Duration::from_millis(100);
 
 #[async_trait]
 pub trait HttpEngine: Clone + Send + Sync + 'static {
     async fn execute(&self, req: Request<Body>) -> Result<Response<Body>, Error>;
 }
 
+/// Object-safe counterpart of `HttpEngine`, implemented by all engines.
+#[async_trait]
+pub trait DynHttpEngine: Send + Sync {
+    async fn execute_dyn(&self, req: Request<Body>) -> Result<Response<Body>, Error>;
+}
+
+#[async_trait]
+impl<E: HttpEngine> DynHttpEngine for E {
+    async fn execute_dyn(&self, req: Request<Body>) -> Result<Response<Body>, Error> {
+        self.execute(req).await
+    }
+}
+
+/// An engine for any of the supported interfaces, selected at runtime.
+#[derive(Clone)]
+pub struct BoxedEngine(Arc<dyn DynHttpEngine>);
+
+impl BoxedEngine {
+    pub fn new(engine: impl HttpEngine) -> Self {
+        Self(Arc::new(engine))
+    }
+}
+
+#[async_trait]
+impl HttpEngine for BoxedEngine {
+    async fn execute(&self, req: Request<Body>) -> Result<Response<Body>, Error> {
+        self.0.execute_dyn(req).await
+    }
+}
+
+#[async_trait]
+impl<E: HttpEngine> HttpEngine for Reloadable<E> {
+    async fn execute(&self, req: Request<Body>) -> Result<Response<Body>, Error> {
+        self.current().execute(req).await
+    }
+}
+
+#[derive(Clone)]
 pub struct Trigger {
     pub address: String,
+    /// Deadline after which a request is answered with 504 Gateway Timeout,
+    /// even if the engine is still blocked in a host call.
+    pub timeout: Option<Duration>,
+    /// Whether error responses have an RFC 7807 problem details body.
+    pub problem_details: bool,
+    /// When set, the trigger serves HTTPS instead of plain HTTP.
+    pub tls: Option<TlsConfig>,
+    /// Proxies whose `X-Forwarded-For` and `X-Forwarded-Proto` headers are
+    /// trusted to identify the client.
+    pub trusted_proxies: Vec<IpNet>,
+    /// Requests with larger bodies are answered with 413 Payload Too Large
+    /// by engines that buffer the body before invoking the guest.
+    pub max_request_body_bytes: Option<usize>,
 }
 
 impl Trigger {
     pub async fn run(&self, runtime: impl HttpEngine) -> Result<(), Error> {
-        let mk_svc = make_service_fn(move |_: &AddrStream| {
-            let r = runtime.clone();
+        let addr: SocketAddr = self.address.parse()?;
+        if let Some(tls) = &self.tls {
+            return self.run_tls(addr, tls, runtime).await;
+        }
+
+        let trigger = Arc::new(self.clone());
+        let mk_svc = make_service_fn(move |conn: &AddrStream| {
+            let (r, trigger) = (runtime.clone(), trigger.clone());
+            let (peer, local) = (conn.remote_addr(), conn.local_addr());
             async move {
                 Ok::<_, Error>(service_fn(move |req| {
-                    let r2 = r.clone();
-                    async move { r2.execute(req).await }
+                    trigger.clone().handle(r.clone(), req, peer, local)
                 }))
             }
         });
 
-        let addr: SocketAddr = self.address.parse()?;
         Server::bind(&addr).serve(mk_svc).await?;
 
         Ok(())
     }
+
+    /// Accept TLS connections, using the latest certificates for each handshake.
+    async fn run_tls(
+        &self,
+        addr: SocketAddr,
+        tls: &TlsConfig,
+        runtime: impl HttpEngine,
+    ) -> Result<(), Error> {
+        let trigger = Arc::new(self.clone());
+        let server_config = tls.watch()?;
+        let listener = TcpListener::bind(addr).await?;
+
+        loop {
+            let (stream, peer) = match listener.accept().await {
+                Ok(conn) => conn,
+                Err(e) => {
+                    log::warn!("Cannot accept connection: {}", e);
+                    tokio::time::sleep(ACCEPT_ERROR_DELAY).await;
+                    continue;
+                }
+            };
+            // The connection can be reset before it is handled.
+            let local = match stream.local_addr() {
+                Ok(local) => local,
+                Err(e) => {
+                    log::warn!("Cannot get the local address of {}: {}", peer, e);
+                    continue;
+                }
+            };
+            let acceptor = TlsAcceptor::from(server_config.current());
+            let (r, trigger) = (runtime.clone(), trigger.clone());
+
+            tokio::spawn(async move {
+                let handshake =
+                    tokio::time::timeout(TLS_HANDSHAKE_TIMEOUT, acceptor.accept(stream));
+                let stream = match handshake.await {
+                    Ok(Ok(stream)) => stream,
+                    Ok(Err(e)) => {
+                        log::warn!("TLS handshake with {} failed: {}", peer, e);
+                        return;
+                    }
+                    Err(_) => {
+                        log::warn!(
+                            "TLS handshake with {} did not complete within {:?}",
+                            peer,
+                            TLS_HANDSHAKE_TIMEOUT
+                        );
+                        return;
+                    }
+                };
+                let svc =
+                    service_fn(move |req| trigger.clone().handle(r.clone(), req, peer, local));
+                if let Err(e) = Http::new().serve_connection(stream, svc).await {
+                    log::debug!("Connection with {} closed: {}", peer, e);
+                }
+            });
+        }
+    }
+
+    /// Handle a request received from `peer` on `local`, answering
+    /// failures with an error response.
+    async fn handle(
+        self: Arc<Self>,
+        runtime: impl HttpEngine,
+        mut req: Request<Body>,
+        peer: SocketAddr,
+        local: SocketAddr,
+    ) -> Result<Response<Body>, Infallible> {
+        let conn = ConnectionInfo::resolve(
+            req.headers(),
+            peer,
+            local,
+            self.tls.is_some(),
+            &self.trusted_proxies,
+        );
+        strip_synthetic_headers(req.headers_mut());
+        req.extensions_mut().insert(conn);
+        if let Some(limit) = self.max_request_body_bytes {
+            req.extensions_mut().insert(BodyLimit(limit));
+        }
+
+        let res = match Self::execute(runtime, req, self.timeout).await {
+            Ok(res) => res,
+            Err(e) => error_response(&e, self.problem_details),
+        };
+
+        Ok(res)
+    }
+
+    /// Execute a request, failing with `HttpError::Timeout` if the engine
+    /// does not complete before the deadline.
+    async fn execute(
+        runtime: impl HttpEngine,
+        req: Request<Body>,
+        timeout: Option<Duration>,
+    ) -> Result<Response<Body>, Error> {
+        let timeout = match timeout {
+            Some(timeout) => timeout,
+            None => return runtime.execute(req).await,
+        };
+
+        // The engine runs the guest synchronously, so it is spawned on its own
+        // task to keep the deadline observable while the guest is blocked.
+        let exec = tokio::spawn(async move { runtime.execute(req).await });
+        match tokio::time::timeout(timeout, exec).await {
+            Ok(res) => res?,
+            Err(_) => Err(HttpError::Timeout(anyhow::anyhow!(
+                "request did not complete within {:?}",
+                timeout
+            ))
+            .into()),
+        }
+    }
 }
@@ -0,0 +1,26 @@
+//! Helpers shared by the tests of the host capabilities.
+#![allow(dead_code)]
+
+use glass_engine::{Config, ModuleSource};
+use glass_http::{HttpEngine, WagiEngine};
+use hyper::{body, Body, Request};
+
+/// Wagi guest calling the host capability selected by its path: `/config`,
+/// `/kv`, `/publish` or `/sqlite`.
+const WAGI_CAPABILITIES_MODULE: &str =
+    "tests/wagi-capabilities/target/wasm32-wasi/release/wagi-capabilities.wasm";
+
+/// Build an engine running the capabilities guest with `config`.
+pub fn capabilities(config: &Config) -> WagiEngine {
+    let module = ModuleSource::File(WAGI_CAPABILITIES_MODULE.to_string());
+    WagiEngine::build(&module, config).unwrap()
+}
+
+/// Send `req` to the engine, returning the status and body.
+pub async fn send(e: &impl HttpEngine, req: Request<Body>) -> (u16, String) {
+    let res = e.execute(req).await.unwrap();
+    let status = res.status().as_u16();
+    let body = body::to_bytes(res.into_body()).await.unwrap();
+
+    (status, String::from_utf8(body.to_vec()).unwrap())
+}
@@ -0,0 +1,87 @@
+use glass_http::{connection::strip_synthetic_headers, ConnectionInfo};
+use hyper::{header::HeaderMap, Version};
+use ipnet::IpNet;
+
+fn headers(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    for (name, value) in pairs {
+        headers.append(*name, value.parse().unwrap());
+    }
+    headers
+}
+
+fn resolve(headers: &HeaderMap, peer: &str, trusted: &[&str]) -> ConnectionInfo {
+    let trusted: Vec<IpNet> = trusted.iter().map(|n| n.parse().unwrap()).collect();
+    ConnectionInfo::resolve(
+        headers,
+        peer.parse().unwrap(),
+        "127.0.0.1:3000".parse().unwrap(),
+        false,
+        &trusted,
+    )
+}
+
+#[test]
+fn test_untrusted_peer() {
+    let headers = headers(&[
+        ("x-forwarded-for", "203.0.113.7"),
+        ("x-forwarded-proto", "https"),
+    ]);
+    let conn = resolve(&headers, "198.51.100.1:4000", &["10.0.0.0/8"]);
+
+    assert_eq!(conn.remote_addr.to_string(), "198.51.100.1");
+    assert_eq!(conn.scheme, "http");
+}
+
+#[test]
+fn test_trusted_proxy_chain() {
+    let headers = headers(&[
+        ("x-forwarded-for", "192.0.2.1, 203.0.113.7"),
+        ("x-forwarded-for", "10.0.0.2"),
+        ("x-forwarded-proto", "https"),
+    ]);
+    let conn = resolve(&headers, "10.0.0.1:4000", &["10.0.0.0/8"]);
+
+    // The spoofable leftmost address is ignored in favor of the last
+    // address that is not a trusted proxy.
+    assert_eq!(conn.remote_addr.to_string(), "203.0.113.7");
+    assert_eq!(conn.peer_addr.to_string(), "10.0.0.1:4000");
+    assert_eq!(conn.scheme, "https");
+}
+
+#[test]
+fn test_forwarded_with_ports() {
+    let headers = headers(&[(
+        "x-forwarded-for",
+        "192.0.2.1, [2001:db8::7]:5000, 10.0.0.2:6000",
+    )]);
+    let conn = resolve(&headers, "10.0.0.1:4000", &["10.0.0.0/8"]);
+
+    assert_eq!(conn.remote_addr.to_string(), "2001:db8::7");
+}
+
+#[test]
+fn test_forwarded_stops_at_invalid_entry() {
+    let headers = headers(&[("x-forwarded-for", "192.0.2.1, unknown, 10.0.0.2")]);
+    let conn = resolve(&headers, "10.0.0.1:4000", &["10.0.0.0/8"]);
+
+    // The entries left of the invalid one were not added by a trusted
+    // proxy, so the client is the last trusted proxy that forwarded it.
+    assert_eq!(conn.remote_addr.to_string(), "10.0.0.2");
+}
+
+#[test]
+fn test_synthetic_headers() {
+    let conn = resolve(&HeaderMap::new(), "198.51.100.1:4000", &[]);
+    let synthetic = conn.headers(Version::HTTP_11);
+    assert!(synthetic.contains(&(
+        "x-glass-remote-addr".to_string(),
+        "198.51.100.1".to_string()
+    )));
+    assert!(synthetic.contains(&("x-glass-http-version".to_string(), "HTTP/1.1".to_string())));
+
+    let mut spoofed = headers(&[("x-glass-remote-addr", "192.0.2.1"), ("x-other", "1")]);
+    strip_synthetic_headers(&mut spoofed);
+    assert!(!spoofed.contains_key("x-glass-remote-addr"));
+    assert!(spoofed.contains_key("x-other"));
+}
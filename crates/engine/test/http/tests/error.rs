@@ -0,0 +1,53 @@
+use glass_engine::ExecutionError;
+use glass_http::{error::error_response, HttpError};
+use std::time::Duration;
+
+#[test]
+fn test_error_statuses() {
+    let cases = vec![
+        (
+            HttpError::instantiation(anyhow::anyhow!("missing export")),
+            500,
+        ),
+        (HttpError::guest_trap(anyhow::anyhow!("unreachable")), 502),
+        (
+            HttpError::invalid_response(anyhow::anyhow!("bad header")),
+            502,
+        ),
+        (
+            HttpError::PayloadTooLarge(anyhow::anyhow!("too large")).into(),
+            413,
+        ),
+        (
+            HttpError::guest_trap(
+                anyhow::anyhow!("trap").context(ExecutionError::OutOfFuel { budget: 1 }),
+            ),
+            503,
+        ),
+        (
+            HttpError::guest_trap(anyhow::anyhow!("trap").context(ExecutionError::Timeout {
+                timeout: Duration::from_secs(1),
+            })),
+            504,
+        ),
+        (anyhow::anyhow!("unclassified"), 500),
+    ];
+
+    for (err, status) in cases {
+        let res = error_response(&err, false);
+        assert_eq!(res.status(), status);
+        assert!(res.headers().contains_key("x-correlation-id"));
+    }
+}
+
+#[tokio::test]
+async fn test_problem_details_hide_error() {
+    let err = HttpError::guest_trap(anyhow::anyhow!("secret internal detail"));
+    let res = error_response(&err, true);
+
+    assert_eq!(res.headers()["content-type"], "application/problem+json");
+    let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+    let body = std::str::from_utf8(&body).unwrap();
+    assert!(body.contains("\"status\":502"));
+    assert!(!body.contains("secret"));
+}
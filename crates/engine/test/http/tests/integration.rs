@@ -1,10 +1,17 @@
-use glass_engine::WasiExecutionContextBuilder;
-use glass_http::{Engine, HttpEngine};
+use glass_engine::{Config, ModuleSource, WasiExecutionContextBuilder};
+use glass_http::{
+    BodyLimit, ConnectionInfo, Engine, EngineV02, HttpEngine, HttpError, RoutePattern,
+    StreamingEngine,
+};
 use hyper::body;
 use std::sync::Arc;
 
 const SIMPLE_RUST_MODULE: &str = "tests/rust/target/wasm32-wasi/release/simple_rust.wasm";
 const SIMPLE_C_MODULE: &str = "tests/c/ctest.wasm";
+const SIMPLE_RUST_V02_MODULE: &str =
+    "tests/rust-v02/target/wasm32-wasi/release/simple_rust_v02.wasm";
+const SIMPLE_RUST_STREAM_MODULE: &str =
+    "tests/rust-stream/target/wasm32-wasi/release/simple_rust_stream.wasm";
 
 #[tokio::test]
 async fn test_rust_handler() {
@@ -25,6 +32,170 @@ async fn test_c_handler() {
     test_example(SIMPLE_C_MODULE, exp_status, exp_body).await;
 }
 
+#[tokio::test]
+async fn test_v01_method_not_allowed() {
+    let req = http::Request::builder()
+        .method("OPTIONS")
+        .uri("https://www.rust-lang.org/")
+        .body(body::Body::empty())
+        .unwrap();
+    let e = Engine(Arc::new(
+        WasiExecutionContextBuilder::build_default(SIMPLE_C_MODULE).unwrap(),
+    ));
+
+    let res = e.execute(req).await.unwrap();
+    assert_eq!(405, res.status());
+}
+
+#[tokio::test]
+async fn test_v02_methods() {
+    let module = ModuleSource::File(SIMPLE_RUST_V02_MODULE.to_string());
+    let e = EngineV02::build(&module, &Config::default()).unwrap();
+
+    for method in &["GET", "HEAD", "OPTIONS", "TRACE", "PURGE"] {
+        let req = http::Request::builder()
+            .method(*method)
+            .uri("https://www.rust-lang.org/")
+            .body(body::Body::empty())
+            .unwrap();
+
+        let res = e.execute(req).await.unwrap();
+        assert_eq!(200, res.status());
+        let body_bytes = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        assert_eq!(method.as_bytes(), &body_bytes[..]);
+    }
+}
+
+#[tokio::test]
+async fn test_v02_raw_and_repeated_headers() {
+    let module = ModuleSource::File(SIMPLE_RUST_V02_MODULE.to_string());
+    let e = EngineV02::build(&module, &Config::default()).unwrap();
+
+    let req = http::Request::builder()
+        .method("GET")
+        .uri("https://www.rust-lang.org/")
+        .header("set-cookie", "a=1")
+        .header("set-cookie", "b=2")
+        .header("x-raw", http::HeaderValue::from_bytes(b"caf\xe9").unwrap())
+        .body(body::Body::empty())
+        .unwrap();
+
+    let res = e.execute(req).await.unwrap();
+    let cookies: Vec<_> = res.headers().get_all("set-cookie").iter().collect();
+    assert_eq!(cookies, vec!["a=1", "b=2"]);
+    assert_eq!(res.headers()["x-raw"].as_bytes(), b"caf\xe9");
+}
+
+#[tokio::test]
+async fn test_v02_route_and_query_params() {
+    let module = ModuleSource::File(SIMPLE_RUST_V02_MODULE.to_string());
+    let e = EngineV02::build(&module, &Config::default()).unwrap();
+
+    let route: RoutePattern = "/users/:id".parse().unwrap();
+    let mut req = http::Request::builder()
+        .method("GET")
+        .uri("https://www.rust-lang.org/users/j%C3%BCrgen?tag=a&tag=b%20c")
+        .body(body::Body::empty())
+        .unwrap();
+    let params = route.captures(req.uri().path()).unwrap();
+    req.extensions_mut().insert(params);
+
+    let res = e.execute(req).await.unwrap();
+    let id: Vec<_> = res.headers().get_all("x-param-id").iter().collect();
+    assert_eq!(id, vec!["jürgen".as_bytes()]);
+    let tags: Vec<_> = res.headers().get_all("x-param-tag").iter().collect();
+    assert_eq!(tags, vec!["a", "b c"]);
+}
+
+#[tokio::test]
+async fn test_v02_connection_info() {
+    let module = ModuleSource::File(SIMPLE_RUST_V02_MODULE.to_string());
+    let e = EngineV02::build(&module, &Config::default()).unwrap();
+
+    let mut req = http::Request::builder()
+        .method("GET")
+        .uri("https://www.rust-lang.org/")
+        .body(body::Body::empty())
+        .unwrap();
+    req.extensions_mut().insert(ConnectionInfo {
+        remote_addr: "203.0.113.7".parse().unwrap(),
+        peer_addr: "10.0.0.1:4000".parse().unwrap(),
+        local_addr: "127.0.0.1:3000".parse().unwrap(),
+        scheme: "https".to_string(),
+    });
+
+    let res = e.execute(req).await.unwrap();
+    assert_eq!(res.headers()["x-remote-addr"], "203.0.113.7");
+    assert_eq!(res.headers()["x-scheme"], "https");
+}
+
+#[tokio::test]
+async fn test_v01_non_utf8_header() {
+    let req = http::Request::builder()
+        .method("GET")
+        .uri("https://www.rust-lang.org/")
+        .header("x-raw", http::HeaderValue::from_bytes(b"caf\xe9").unwrap())
+        .body(body::Body::empty())
+        .unwrap();
+    let e = Engine(Arc::new(
+        WasiExecutionContextBuilder::build_default(SIMPLE_RUST_MODULE).unwrap(),
+    ));
+
+    let res = e.execute(req).await.unwrap();
+    assert_eq!(200, res.status());
+}
+
+#[tokio::test]
+async fn test_v01_header_with_tab() {
+    let req = http::Request::builder()
+        .method("GET")
+        .uri("https://www.rust-lang.org/")
+        .header("x-tab", "a\tb")
+        .body(body::Body::empty())
+        .unwrap();
+    let e = Engine(Arc::new(
+        WasiExecutionContextBuilder::build_default(SIMPLE_RUST_MODULE).unwrap(),
+    ));
+
+    let res = e.execute(req).await.unwrap();
+    assert_eq!(200, res.status());
+}
+
+#[tokio::test]
+async fn test_v01_payload_too_large() {
+    let module = ModuleSource::File(SIMPLE_RUST_MODULE.to_string());
+    let e = Engine::build(&module, &Config::default()).unwrap();
+
+    let req = http::Request::builder()
+        .method("POST")
+        .uri("https://www.rust-lang.org/")
+        .extension(BodyLimit(16))
+        .body(body::Body::from(vec![0u8; 17]))
+        .unwrap();
+
+    let err = e.execute(req).await.unwrap_err();
+    assert_eq!(err.downcast_ref::<HttpError>().unwrap().status(), 413);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_streaming_echo() {
+    let module = ModuleSource::File(SIMPLE_RUST_STREAM_MODULE.to_string());
+    let e = StreamingEngine::build(&module, &Config::default()).unwrap();
+
+    let payload: Vec<u8> = (0..100_000).map(|i| (i % 251) as u8).collect();
+    let req = http::Request::builder()
+        .method("POST")
+        .uri("https://www.rust-lang.org/")
+        .body(body::Body::from(payload.clone()))
+        .unwrap();
+
+    let res = e.execute(req).await.unwrap();
+    assert_eq!(200, res.status());
+    assert_eq!(res.headers()["x-streamed"], "true");
+    let body_bytes = hyper::body::to_bytes(res.into_body()).await.unwrap();
+    assert_eq!(payload, body_bytes.to_vec());
+}
+
 async fn test_example(entrypoint: &str, exp_status: u16, exp_body: Vec<u8>) {
     let req = http::Request::builder()
         .method("GET")
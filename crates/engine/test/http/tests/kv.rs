@@ -0,0 +1,48 @@
+use glass_engine::{kv::MemoryStore, Config, KvStore};
+use glass_http::WagiEngine;
+use hyper::{Body, Request};
+use std::sync::Arc;
+
+mod common;
+
+/// Send a request for `key`, returning the status and body.
+async fn run(e: &WagiEngine, method: &str, key: &str, value: &str) -> (u16, String) {
+    let req = Request::builder()
+        .method(method)
+        .uri(format!("/kv?{}", key))
+        .body(Body::from(value.to_string()))
+        .unwrap();
+    common::send(e, req).await
+}
+
+#[tokio::test]
+async fn test_kv_guest() {
+    let store = Arc::new(MemoryStore::default());
+    let config = Config {
+        kv_store: Some(store.clone()),
+        kv_namespace: Some("shared".to_string()),
+        ..Config::default()
+    };
+    let e = common::capabilities(&config);
+
+    assert_eq!(run(&e, "GET", "greeting", "").await.0, 404);
+    assert_eq!(run(&e, "POST", "greeting", "hello").await, (200, "".into()));
+    assert_eq!(run(&e, "GET", "greeting", "").await, (200, "hello".into()));
+    // The guest uses the namespace configured on the host.
+    assert_eq!(
+        store.get("shared", "greeting").unwrap(),
+        Some(b"hello".to_vec())
+    );
+
+    store.set("shared", "name", b"glass", None).unwrap();
+    assert_eq!(run(&e, "GET", "", "").await, (200, "greeting,name".into()));
+
+    assert_eq!(
+        run(&e, "DELETE", "greeting", "").await,
+        (200, "true".into())
+    );
+    assert_eq!(
+        run(&e, "DELETE", "greeting", "").await,
+        (200, "false".into())
+    );
+}
@@ -0,0 +1,62 @@
+use glass_http::{
+    params::{query_params, serialize_param},
+    RouteParams, RoutePattern,
+};
+use hyper::{Body, Request};
+
+fn request(uri: &str) -> Request<Body> {
+    Request::builder().uri(uri).body(Body::empty()).unwrap()
+}
+
+#[test]
+fn test_query_params() {
+    let req = request("http://localhost/?a=1&b=x%20y&a=2&c=d+e&flag");
+    assert_eq!(
+        query_params(&req),
+        vec![
+            ("a".to_string(), "1".to_string()),
+            ("b".to_string(), "x y".to_string()),
+            ("a".to_string(), "2".to_string()),
+            ("c".to_string(), "d e".to_string()),
+            ("flag".to_string(), "".to_string()),
+        ]
+    );
+
+    assert!(query_params(&request("http://localhost/")).is_empty());
+}
+
+#[test]
+fn test_serialize_param() {
+    let params = |uri| -> Vec<String> {
+        query_params(&request(uri))
+            .iter()
+            .map(|(k, v)| serialize_param(k, v))
+            .collect()
+    };
+
+    // The first `=` separates the key from the value.
+    assert_eq!(params("http://localhost/?a%3Db=c"), vec!["a%3Db=c"]);
+    assert_eq!(params("http://localhost/?a=b%3Dc"), vec!["a=b=c"]);
+    assert_eq!(params("http://localhost/?a%25=1&b"), vec!["a%25=1", "b="]);
+}
+
+#[test]
+fn test_route_captures() {
+    let route: RoutePattern = "/users/:id/posts/:post".parse().unwrap();
+
+    assert_eq!(
+        route.captures("/users/42/posts/hello%2Fworld"),
+        Some(RouteParams(vec![
+            ("id".to_string(), "42".to_string()),
+            ("post".to_string(), "hello/world".to_string()),
+        ]))
+    );
+    assert_eq!(route.captures("/users/42"), None);
+    assert_eq!(route.captures("/groups/42/posts/1"), None);
+}
+
+#[test]
+fn test_invalid_route() {
+    assert!("users/:id".parse::<RoutePattern>().is_err());
+    assert!("/users/:".parse::<RoutePattern>().is_err());
+}
@@ -0,0 +1,44 @@
+use glass_engine::{
+    publish::{ChannelPublisher, Message},
+    Config, Publisher,
+};
+use glass_http::WagiEngine;
+use hyper::{Body, Request};
+use std::sync::Arc;
+
+mod common;
+
+/// Publish `payload` to `topic`, returning the status and body.
+async fn publish(e: &WagiEngine, topic: &str, payload: &str) -> (u16, String) {
+    let req = Request::builder()
+        .method("POST")
+        .uri(format!("/publish?{}", topic))
+        .header("content-type", "text/plain")
+        .body(Body::from(payload.to_string()))
+        .unwrap();
+    common::send(e, req).await
+}
+
+#[tokio::test]
+async fn test_publish_guest() {
+    let publisher = ChannelPublisher::default();
+    let mut messages = publisher.subscribe().unwrap();
+    let config = Config {
+        publisher: Some(Arc::new(publisher)),
+        ..Config::default()
+    };
+    let e = common::capabilities(&config);
+
+    assert_eq!(publish(&e, "orders", "hello").await, (200, "".into()));
+    assert_eq!(
+        *messages.try_recv().unwrap(),
+        Message {
+            topic: "orders".to_string(),
+            payload: b"hello".to_vec(),
+            attributes: vec![("content-type".to_string(), "text/plain".to_string())],
+        }
+    );
+
+    assert_eq!(publish(&e, "", "lost").await, (500, "InvalidTopic".into()));
+    assert!(messages.try_recv().is_err());
+}
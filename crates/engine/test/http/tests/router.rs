@@ -0,0 +1,81 @@
+use anyhow::Error;
+use async_trait::async_trait;
+use glass_http::{HttpEngine, RouteParams, RoutePattern, Router};
+use hyper::{Body, Request, Response};
+
+/// Engine answering with its name followed by the route parameters.
+#[derive(Clone)]
+struct Named(&'static str);
+
+#[async_trait]
+impl HttpEngine for Named {
+    async fn execute(&self, req: Request<Body>) -> Result<Response<Body>, Error> {
+        let mut body = self.0.to_string();
+        let params = req.extensions().get::<RouteParams>().cloned();
+        for (k, v) in params.unwrap_or_default().0 {
+            body.push_str(&format!(" {}={}", k, v));
+        }
+        Ok(Response::new(Body::from(body)))
+    }
+}
+
+fn route(pattern: &str) -> RoutePattern {
+    pattern.parse().unwrap()
+}
+
+async fn get(router: &Router<Named>, path: &str) -> (u16, String) {
+    let req = Request::builder().uri(path).body(Body::empty()).unwrap();
+    let res = router.execute(req).await.unwrap();
+    let status = res.status().as_u16();
+    let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+    (status, String::from_utf8(body.to_vec()).unwrap())
+}
+
+#[tokio::test]
+async fn test_longest_match() {
+    let router = Router::new()
+        .route(route("/*"), Named("root"))
+        .route(route("/api/*"), Named("api"))
+        .route(route("/api/users/:id"), Named("user"))
+        .route(route("/api/users/me"), Named("me"))
+        .route(route("/api/*/raw"), Named("raw"));
+
+    assert_eq!(get(&router, "/").await, (200, "root".to_string()));
+    assert_eq!(get(&router, "/index.html").await, (200, "root".to_string()));
+    assert_eq!(get(&router, "/api").await, (200, "api".to_string()));
+    assert_eq!(get(&router, "/api/a/b/c").await, (200, "api".to_string()));
+    assert_eq!(get(&router, "/api/users/me").await, (200, "me".to_string()));
+    assert_eq!(
+        get(&router, "/api/users/42").await,
+        (200, "user id=42".to_string())
+    );
+    assert_eq!(
+        get(&router, "/api/files/raw").await,
+        (200, "raw".to_string())
+    );
+}
+
+#[tokio::test]
+async fn test_not_found() {
+    let router = Router::new().route(route("/about"), Named("about"));
+    assert_eq!(get(&router, "/about").await, (200, "about".to_string()));
+    assert_eq!(get(&router, "/about/us").await.0, 404);
+
+    let router = router.not_found(Named("fallback"));
+    assert_eq!(
+        get(&router, "/about/us").await,
+        (200, "fallback".to_string())
+    );
+}
+
+#[test]
+fn test_route_order() {
+    let router = Router::new()
+        .route(route("/*"), Named("root"))
+        .route(route("/a/:b"), Named("capture"))
+        .route(route("/a/*"), Named("prefix"))
+        .route(route("/a/b"), Named("exact"));
+
+    let patterns: Vec<_> = router.patterns().iter().map(|p| p.to_string()).collect();
+    assert_eq!(patterns, vec!["/a/b", "/a/:b", "/a/*", "/*"]);
+}
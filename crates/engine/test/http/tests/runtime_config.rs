@@ -0,0 +1,32 @@
+use glass_engine::{Config, ConfigSource, RuntimeConfig};
+use glass_http::WagiEngine;
+use hyper::{Body, Request};
+
+mod common;
+
+/// Get the value of `key`, returning the status and body.
+async fn get(e: &WagiEngine, key: &str) -> (u16, String) {
+    let req = Request::builder()
+        .uri(format!("/config?{}", key))
+        .body(Body::empty())
+        .unwrap();
+    common::send(e, req).await
+}
+
+#[tokio::test]
+async fn test_config_guest() {
+    let runtime_config = RuntimeConfig::new(vec![ConfigSource::Values(vec![
+        ("greeting".to_string(), "hello".to_string()),
+        ("db.url".to_string(), "postgres://db".to_string()),
+    ])])
+    .unwrap();
+    let config = Config {
+        runtime_config: Some(runtime_config),
+        ..Config::default()
+    };
+    let e = common::capabilities(&config);
+
+    assert_eq!(get(&e, "greeting").await, (200, "hello".into()));
+    assert_eq!(get(&e, "db.url").await, (200, "postgres://db".into()));
+    assert_eq!(get(&e, "missing").await.0, 404);
+}
@@ -0,0 +1,24 @@
+use deislabs_http_stream_v01::Request;
+
+witx_bindgen_rust::export!("../../deislabs_http_stream_v01.witx");
+witx_bindgen_rust::import!("../../deislabs_http_stream_host_v01.witx");
+
+struct DeislabsHttpStreamV01 {}
+
+impl deislabs_http_stream_v01::DeislabsHttpStreamV01 for DeislabsHttpStreamV01 {
+    fn handle_request_stream(req: Request) {
+        let (_, uri, _, _, _) = req;
+        println!("uri: {:?}", uri);
+
+        // Echo the request body back one small chunk at a time, to test
+        // bodies larger than a single chunk are streamed both ways.
+        deislabs_http_stream_host_v01::start_response(200, &[("x-streamed", b"true")]).unwrap();
+        loop {
+            let chunk = deislabs_http_stream_host_v01::read_body(1024).unwrap();
+            if chunk.is_empty() {
+                break;
+            }
+            deislabs_http_stream_host_v01::write_body(&chunk).unwrap();
+        }
+    }
+}
@@ -0,0 +1,37 @@
+use deislabs_http_v02::{Method, Request, Response};
+
+witx_bindgen_rust::export!("../../deislabs_http_v02.witx");
+
+struct DeislabsHttpV02 {}
+
+impl deislabs_http_v02::DeislabsHttpV02 for DeislabsHttpV02 {
+    fn handle_request(req: Request) -> Response {
+        let (method, uri, mut headers, params, _, conn) = req;
+        println!("uri: {:?}", uri);
+
+        let method = match method {
+            Method::Get => "GET".to_string(),
+            Method::Post => "POST".to_string(),
+            Method::Put => "PUT".to_string(),
+            Method::Delete => "DELETE".to_string(),
+            Method::Patch => "PATCH".to_string(),
+            Method::Head => "HEAD".to_string(),
+            Method::Options => "OPTIONS".to_string(),
+            Method::Connect => "CONNECT".to_string(),
+            Method::Trace => "TRACE".to_string(),
+            Method::Other(m) => m,
+        };
+
+        // Echo the request headers back to test they round-trip unchanged,
+        // followed by the parameters as `x-param-<key>` headers and some of
+        // the connection info.
+        for (k, v) in params.unwrap_or_default() {
+            headers.push((format!("x-param-{}", k), v.into_bytes()));
+        }
+        if let Some(conn) = conn {
+            headers.push(("x-remote-addr".to_string(), conn.remote_addr.into_bytes()));
+            headers.push(("x-scheme".to_string(), conn.scheme.into_bytes()));
+        }
+        (200, Some(headers), Some(method.into_bytes()))
+    }
+}
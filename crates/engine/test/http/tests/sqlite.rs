@@ -0,0 +1,117 @@
+use glass_engine::{Config, SqliteDatabase};
+use glass_http::WagiEngine;
+use hyper::{Body, Request};
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+mod common;
+
+/// Run `statement` on `database`, returning the status and body.
+async fn run(
+    e: &WagiEngine,
+    method: &str,
+    database: &str,
+    params: &str,
+    statement: &str,
+) -> (u16, String) {
+    let req = Request::builder()
+        .method(method)
+        .uri(format!("/sqlite?{}", params))
+        .header("x-database", database)
+        .body(Body::from(statement.to_string()))
+        .unwrap();
+    common::send(e, req).await
+}
+
+fn database(name: &str, path: &Path, read_only: bool) -> SqliteDatabase {
+    SqliteDatabase {
+        name: name.to_string(),
+        path: path.to_path_buf(),
+        read_only,
+    }
+}
+
+#[tokio::test]
+async fn test_sqlite_queries() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("todos.db");
+
+    let config = Config {
+        sqlite_databases: vec![
+            database("todos", &path, false),
+            database("todos-ro", &path, true),
+        ],
+        sqlite_timeout: Some(Duration::from_millis(200)),
+        ..Config::default()
+    };
+    let e = common::capabilities(&config);
+
+    let create = "CREATE TABLE todos (id INTEGER PRIMARY KEY, title TEXT NOT NULL, done REAL)";
+    assert_eq!(
+        run(&e, "POST", "todos", "", create).await,
+        (200, "0".into())
+    );
+    let insert = "INSERT INTO todos (id, title) VALUES (?1, ?2)";
+    assert_eq!(
+        run(&e, "POST", "todos", "1&write", insert).await,
+        (200, "1".into())
+    );
+    assert_eq!(
+        run(&e, "POST", "todos", "2&test", insert).await,
+        (200, "1".into())
+    );
+
+    let select = "SELECT id, title, done FROM todos WHERE id >= ?1 ORDER BY id";
+    assert_eq!(
+        run(&e, "GET", "todos-ro", "1", select).await,
+        (200, "id,title,done\n1,write,NULL\n2,test,NULL\n".into())
+    );
+
+    assert_eq!(
+        run(&e, "POST", "todos-ro", "3&deploy", insert).await,
+        (500, "ReadOnly".into())
+    );
+    assert_eq!(
+        run(&e, "POST", "todos", "1&again", insert).await,
+        (500, "ConstraintViolation".into())
+    );
+    assert_eq!(
+        run(&e, "GET", "todos", "", "SELECT * FROM missing").await,
+        (500, "InvalidQuery".into())
+    );
+    assert_eq!(
+        run(&e, "GET", "other", "", "SELECT 1").await,
+        (500, "NoSuchDatabase".into())
+    );
+
+    let forever =
+        "WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n) SELECT count(*) FROM n";
+    assert_eq!(
+        run(&e, "GET", "todos", "", forever).await,
+        (500, "Timeout".into())
+    );
+
+    // Guests cannot reach other database files through their connection.
+    let other = dir.path().join("other.db");
+    let attach = format!("ATTACH DATABASE '{}' AS other", other.display());
+    assert_eq!(
+        run(&e, "POST", "todos", "", &attach).await,
+        (500, "Denied".into())
+    );
+    let vacuum = format!("VACUUM INTO '{}'", other.display());
+    assert_eq!(run(&e, "POST", "todos", "", &vacuum).await.0, 500);
+    assert!(!other.exists());
+}
+
+#[test]
+fn test_parse_sqlite_database() {
+    assert_eq!(
+        "todos=data/todos.db".parse::<SqliteDatabase>().unwrap(),
+        database("todos", &PathBuf::from("data/todos.db"), false)
+    );
+
+    assert!("todos".parse::<SqliteDatabase>().is_err());
+    assert!("=data/todos.db".parse::<SqliteDatabase>().is_err());
+}
@@ -0,0 +1,69 @@
+use glass_engine::{Config, ModuleSource, StdioPolicy};
+use glass_http::{EngineV02, HttpEngine};
+use hyper::{Body, Request};
+use log::{Log, Metadata, Record};
+use std::sync::Mutex;
+
+const SIMPLE_RUST_V02_MODULE: &str =
+    "tests/rust-v02/target/wasm32-wasi/release/simple_rust_v02.wasm";
+
+/// Logger keeping the messages logged for guests.
+struct GuestLogger(Mutex<Vec<String>>);
+
+impl Log for GuestLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.target() == "glass::guest"
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            self.0.lock().unwrap().push(record.args().to_string());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: GuestLogger = GuestLogger(Mutex::new(Vec::new()));
+
+async fn request(config: &Config) -> Vec<String> {
+    let module = ModuleSource::File(SIMPLE_RUST_V02_MODULE.to_string());
+    let e = EngineV02::build(&module, config).unwrap();
+    let req = Request::builder()
+        .uri("https://www.rust-lang.org/captured")
+        .body(Body::empty())
+        .unwrap();
+    e.execute(req).await.unwrap();
+
+    std::mem::take(&mut *LOGGER.0.lock().unwrap())
+}
+
+// A single test, as the logger is global to the process.
+#[tokio::test]
+async fn test_capture_stdout() {
+    log::set_logger(&LOGGER).unwrap();
+    log::set_max_level(log::LevelFilter::Info);
+
+    let config = Config {
+        stdout: StdioPolicy::Capture { max_bytes: 1024 },
+        ..Config::default()
+    };
+    let logged = request(&config).await;
+    assert_eq!(logged.len(), 1);
+    assert!(logged[0].contains(SIMPLE_RUST_V02_MODULE));
+    assert!(logged[0].ends_with("stdout: uri: \"https://www.rust-lang.org/captured\""));
+
+    let config = Config {
+        stdout: StdioPolicy::Capture { max_bytes: 4 },
+        ..Config::default()
+    };
+    let logged = request(&config).await;
+    assert!(logged[0].ends_with("stdout: uri:"));
+    assert!(logged[1].contains("dropped"));
+
+    let config = Config {
+        stdout: StdioPolicy::Discard,
+        ..Config::default()
+    };
+    assert!(request(&config).await.is_empty());
+}
@@ -0,0 +1,70 @@
+use glass_http::TlsConfig;
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+/// Write a self-signed certificate and its key to `dir`.
+fn self_signed(dir: &Path) -> (PathBuf, PathBuf) {
+    let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+    let (cert_path, key_path) = (dir.join("cert.pem"), dir.join("key.pem"));
+    std::fs::write(&cert_path, cert.serialize_pem().unwrap()).unwrap();
+    std::fs::write(&key_path, cert.serialize_private_key_pem()).unwrap();
+
+    (cert_path, key_path)
+}
+
+#[test]
+fn test_server_config() {
+    let dir = tempfile::tempdir().unwrap();
+    let (cert, key) = self_signed(dir.path());
+    let tls = TlsConfig {
+        cert: cert.clone(),
+        key,
+        client_ca: None,
+    };
+    let config = tls.server_config().unwrap();
+    assert_eq!(config.alpn_protocols[0], b"h2");
+
+    // A self-signed certificate is its own CA.
+    let tls = TlsConfig {
+        client_ca: Some(cert),
+        ..tls
+    };
+    tls.server_config().unwrap();
+}
+
+#[test]
+fn test_missing_key() {
+    let dir = tempfile::tempdir().unwrap();
+    let (cert, _) = self_signed(dir.path());
+    let tls = TlsConfig {
+        cert: cert.clone(),
+        key: cert,
+        client_ca: None,
+    };
+
+    let err = tls.server_config().unwrap_err();
+    assert!(err.to_string().contains("no private key"));
+}
+
+#[test]
+fn test_reload() {
+    let dir = tempfile::tempdir().unwrap();
+    let (cert, key) = self_signed(dir.path());
+    let tls = TlsConfig {
+        cert: cert.clone(),
+        key: key.clone(),
+        client_ca: None,
+    };
+    let config = tls.watch().unwrap();
+    let before = config.current();
+
+    let new_dir = tempfile::tempdir().unwrap();
+    let (new_cert, new_key) = self_signed(new_dir.path());
+    std::fs::copy(new_cert, &cert).unwrap();
+    std::fs::copy(new_key, &key).unwrap();
+
+    assert!(config.reload());
+    assert!(!Arc::ptr_eq(&before, &config.current()));
+}
@@ -0,0 +1,239 @@
+use glass_engine::{Config, ModuleSource, Reloadable};
+use glass_http::{HttpEngine, TlsConfig, Trigger, WagiEngine};
+use hyper::{client::conn, Body, Client, Request, StatusCode};
+use rcgen::{BasicConstraints, Certificate, CertificateParams, DnType, IsCa};
+use std::{convert::TryFrom, net::SocketAddr, path::Path, sync::Arc, time::Duration};
+use tokio_rustls::{
+    rustls::{self, ClientConfig, RootCertStore, ServerName},
+    TlsConnector,
+};
+
+/// A Wagi module that never completes.
+const SPINNING_MODULE: &str = r#"
+(module
+    (memory (export "memory") 1)
+    (func (export "_start") (loop (br 0))))
+"#;
+
+const TIMEOUT: Duration = Duration::from_millis(200);
+
+/// A Wagi module answering every request with `status`.
+fn status_module(status: u16) -> String {
+    format!(
+        r#"
+(module
+    (import "wasi_snapshot_preview1" "fd_write"
+        (func $fd_write (param i32 i32 i32 i32) (result i32)))
+    (memory (export "memory") 1)
+    (data (i32.const 16) "Status: {}\n\n")
+    (func (export "_start")
+        (i32.store (i32.const 0) (i32.const 16))
+        (i32.store (i32.const 4) (i32.const 13))
+        (drop (call $fd_write (i32.const 1) (i32.const 0) (i32.const 1) (i32.const 8)))))
+"#,
+        status
+    )
+}
+
+fn trigger() -> Trigger {
+    Trigger {
+        address: String::new(),
+        timeout: None,
+        problem_details: false,
+        tls: None,
+        trusted_proxies: Vec::new(),
+        max_request_body_bytes: None,
+    }
+}
+
+async fn get(addr: SocketAddr) -> StatusCode {
+    let req = Request::get(format!("http://{}/", addr))
+        .body(Body::empty())
+        .unwrap();
+    Client::new().request(req).await.unwrap().status()
+}
+
+fn wagi(wat: &str, config: &Config) -> WagiEngine {
+    let module = ModuleSource::Bytes {
+        name: "wagi.wat".to_string(),
+        bytes: Arc::new(wat.as_bytes().to_vec()),
+    };
+    WagiEngine::build(&module, config).unwrap()
+}
+
+/// Run `trigger` on a free local port, returning its address once it accepts
+/// connections.
+async fn serve(mut trigger: Trigger, engine: impl HttpEngine) -> SocketAddr {
+    let addr = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    trigger.address = addr.to_string();
+    tokio::spawn(async move { trigger.run(engine).await.unwrap() });
+
+    for _ in 0..100 {
+        if tokio::net::TcpStream::connect(addr).await.is_ok() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    addr
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_timeout() {
+    let config = Config {
+        timeout: Some(TIMEOUT),
+        ..Config::default()
+    };
+    let trigger = Trigger {
+        timeout: config.timeout,
+        ..trigger()
+    };
+    let addr = serve(trigger, wagi(SPINNING_MODULE, &config)).await;
+
+    // The guest is interrupted, so the trigger keeps answering requests.
+    for _ in 0..2 {
+        assert_eq!(get(addr).await, 504);
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_hot_reload_on_module_change() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("wagi.wat");
+    std::fs::write(&path, status_module(201)).unwrap();
+
+    let module = ModuleSource::File(path.to_string_lossy().into_owned());
+    let engine = Reloadable::new(move || WagiEngine::build(&module, &Config::default())).unwrap();
+    engine.watch(&path, Duration::from_millis(50));
+    let addr = serve(trigger(), engine).await;
+    assert_eq!(get(addr).await, 201);
+
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    std::fs::write(&path, status_module(202)).unwrap();
+    for _ in 0..100 {
+        if get(addr).await == 202 {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    panic!("the module was not reloaded");
+}
+
+/// A CA, and certificates it signed for the server and a client.
+struct Pki {
+    ca: Certificate,
+    server: Certificate,
+    client: Certificate,
+}
+
+impl Pki {
+    fn new() -> Self {
+        let mut params = CertificateParams::new(vec![]);
+        params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        params
+            .distinguished_name
+            .push(DnType::CommonName, "Glass test CA");
+        Self {
+            ca: Certificate::from_params(params).unwrap(),
+            server: rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap(),
+            client: rcgen::generate_simple_self_signed(vec!["client".to_string()]).unwrap(),
+        }
+    }
+
+    /// Write the server certificate, its key and the CA to `dir`.
+    fn server_tls(&self, dir: &Path) -> TlsConfig {
+        let tls = TlsConfig {
+            cert: dir.join("cert.pem"),
+            key: dir.join("key.pem"),
+            client_ca: Some(dir.join("ca.pem")),
+        };
+        let cert = self.server.serialize_pem_with_signer(&self.ca).unwrap();
+        std::fs::write(&tls.cert, cert).unwrap();
+        std::fs::write(&tls.key, self.server.serialize_private_key_pem()).unwrap();
+        std::fs::write(
+            tls.client_ca.as_ref().unwrap(),
+            self.ca.serialize_pem().unwrap(),
+        )
+        .unwrap();
+
+        tls
+    }
+
+    /// A client trusting the CA, authenticating with a certificate signed by
+    /// `signer` if there is one.
+    fn client(&self, signer: Option<&Certificate>) -> ClientConfig {
+        let mut roots = RootCertStore::empty();
+        roots
+            .add(&rustls::Certificate(self.ca.serialize_der().unwrap()))
+            .unwrap();
+        let builder = ClientConfig::builder()
+            .with_safe_defaults()
+            .with_root_certificates(roots);
+
+        match signer {
+            Some(signer) => {
+                let cert = self.client.serialize_der_with_signer(signer).unwrap();
+                let key = self.client.serialize_private_key_der();
+                builder
+                    .with_single_cert(vec![rustls::Certificate(cert)], rustls::PrivateKey(key))
+                    .unwrap()
+            }
+            None => builder.with_no_client_auth(),
+        }
+    }
+}
+
+/// Send a request over HTTPS to the trigger at `addr`.
+async fn get_tls(addr: SocketAddr, client: ClientConfig) -> Result<StatusCode, anyhow::Error> {
+    let stream = tokio::net::TcpStream::connect(addr).await?;
+    let name = ServerName::try_from("localhost")?;
+    let stream = TlsConnector::from(Arc::new(client))
+        .connect(name, stream)
+        .await?;
+
+    let (mut sender, connection) = conn::handshake(stream).await?;
+    tokio::spawn(connection);
+    let req = Request::get("/").body(Body::empty())?;
+    Ok(sender.send_request(req).await?.status())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_https_round_trip() {
+    let dir = tempfile::tempdir().unwrap();
+    let pki = Pki::new();
+    let tls = TlsConfig {
+        client_ca: None,
+        ..pki.server_tls(dir.path())
+    };
+    let trigger = Trigger {
+        tls: Some(tls),
+        ..trigger()
+    };
+    let addr = serve(trigger, wagi(&status_module(201), &Config::default())).await;
+
+    let status = get_tls(addr, pki.client(None)).await.unwrap();
+    assert_eq!(status, 201);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_mtls_rejects_unauthenticated_clients() {
+    let dir = tempfile::tempdir().unwrap();
+    let pki = Pki::new();
+    let trigger = Trigger {
+        tls: Some(pki.server_tls(dir.path())),
+        ..trigger()
+    };
+    let addr = serve(trigger, wagi(&status_module(201), &Config::default())).await;
+
+    let status = get_tls(addr, pki.client(Some(&pki.ca))).await.unwrap();
+    assert_eq!(status, 201);
+
+    // Clients without a certificate, or with one signed by another CA,
+    // fail the handshake.
+    assert!(get_tls(addr, pki.client(None)).await.is_err());
+    let other_ca = Pki::new().ca;
+    assert!(get_tls(addr, pki.client(Some(&other_ca))).await.is_err());
+}
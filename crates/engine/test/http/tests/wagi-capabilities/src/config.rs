@@ -0,0 +1,9 @@
+witx_bindgen_rust::import!("../../../../glass_config_v01.witx");
+
+/// Respond with the value of the configuration key in the query string.
+pub fn main() {
+    match glass_config_v01::get(&crate::query()) {
+        Some(value) => crate::respond(Ok::<_, ()>(value)),
+        None => crate::not_found(),
+    }
+}
@@ -0,0 +1,23 @@
+witx_bindgen_rust::import!("../../../../glass_kv_v01.witx");
+
+/// Operate on the key in the query string: GET reads it, POST sets it to
+/// the body and DELETE deletes it. GET without a key lists all keys.
+pub fn main() {
+    let key = crate::query();
+    let result = match crate::method().as_str() {
+        "GET" if key.is_empty() => glass_kv_v01::list_keys("").map(|keys| keys.join(",")),
+        "GET" => match glass_kv_v01::get(&key) {
+            Ok(Some(value)) => Ok(String::from_utf8(value).unwrap()),
+            Ok(None) => return crate::not_found(),
+            Err(e) => Err(e),
+        },
+        "POST" => glass_kv_v01::set(&key, &crate::body(), None).map(|_| String::new()),
+        "DELETE" => glass_kv_v01::delete(&key).map(|deleted| deleted.to_string()),
+        _ => {
+            println!("Status: 405");
+            Ok(String::new())
+        }
+    };
+
+    crate::respond(result);
+}
@@ -0,0 +1,62 @@
+use std::{
+    fmt::Debug,
+    io::{self, Read},
+};
+
+mod config;
+mod kv;
+mod publish;
+mod sqlite;
+
+/// Exercise the host capability selected by the path of the request.
+fn main() {
+    println!("Content-Type: text/plain");
+    match std::env::var("PATH_INFO").unwrap_or_default().as_str() {
+        "/config" => config::main(),
+        "/kv" => kv::main(),
+        "/publish" => publish::main(),
+        "/sqlite" => sqlite::main(),
+        _ => {
+            println!("Status: 404");
+            println!();
+        }
+    }
+}
+
+/// The request method.
+fn method() -> String {
+    std::env::var("REQUEST_METHOD").unwrap()
+}
+
+/// The query string of the request.
+fn query() -> String {
+    std::env::var("QUERY_STRING").unwrap_or_default()
+}
+
+/// The request body.
+fn body() -> Vec<u8> {
+    let mut body = Vec::new();
+    io::stdin().read_to_end(&mut body).unwrap();
+    body
+}
+
+/// Respond with `body`, or with the error as a 500.
+fn respond(result: Result<String, impl Debug>) {
+    match result {
+        Ok(body) => {
+            println!();
+            print!("{}", body);
+        }
+        Err(e) => {
+            println!("Status: 500");
+            println!();
+            print!("{:?}", e);
+        }
+    }
+}
+
+/// Respond with 404 Not Found.
+fn not_found() {
+    println!("Status: 404");
+    println!();
+}
@@ -0,0 +1,11 @@
+witx_bindgen_rust::import!("../../../../glass_publish_v01.witx");
+
+/// Publish the body to the topic in the query string, with the content
+/// type of the request as an attribute.
+pub fn main() {
+    let content_type = std::env::var("CONTENT_TYPE").unwrap_or_default();
+    let attributes = [("content-type", content_type.as_str())];
+    let result = glass_publish_v01::publish(&crate::query(), &crate::body(), &attributes);
+
+    crate::respond(result.map(|_| String::new()));
+}
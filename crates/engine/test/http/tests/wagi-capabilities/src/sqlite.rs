@@ -0,0 +1,52 @@
+use glass_sqlite_v01::{ValueParam, ValueResult};
+
+witx_bindgen_rust::import!("../../../../glass_sqlite_v01.witx");
+
+/// Run the statement in the body on the database named by the
+/// `X-Database` header. POST requests execute it, other requests query it.
+/// The query string holds the parameters, as integers or text.
+pub fn main() {
+    let name = std::env::var("HTTP_X_DATABASE").unwrap_or_default();
+    let query = crate::query();
+    let statement = String::from_utf8(crate::body()).unwrap();
+
+    let params: Vec<ValueParam> = query
+        .split('&')
+        .filter(|p| !p.is_empty())
+        .map(|p| match p.parse() {
+            Ok(i) => ValueParam::Integer(i),
+            Err(_) => ValueParam::Text(p),
+        })
+        .collect();
+
+    let db = match glass_sqlite_v01::open(&name) {
+        Ok(db) => db,
+        Err(e) => return crate::respond(Err::<String, _>(e)),
+    };
+
+    let result = if crate::method() == "POST" {
+        glass_sqlite_v01::execute(db, &statement, &params).map(|changed| changed.to_string())
+    } else {
+        glass_sqlite_v01::query(db, &statement, &params).map(|rows| {
+            let mut body = format!("{}\n", rows.columns.join(","));
+            for row in rows.rows {
+                let values: Vec<String> = row.into_iter().map(format_value).collect();
+                body.push_str(&format!("{}\n", values.join(",")));
+            }
+            body
+        })
+    };
+    glass_sqlite_v01::close(db);
+
+    crate::respond(result);
+}
+
+fn format_value(value: ValueResult) -> String {
+    match value {
+        ValueResult::Null => "NULL".to_string(),
+        ValueResult::Integer(i) => i.to_string(),
+        ValueResult::Real(f) => f.to_string(),
+        ValueResult::Text(s) => s,
+        ValueResult::Blob(b) => format!("<{} bytes>", b.len()),
+    }
+}
@@ -0,0 +1,71 @@
+use glass_engine::{Config, ModuleSource};
+use glass_http::{engine_wagi::parse_cgi_response, HttpEngine, RoutePattern, Router, WagiEngine};
+use hyper::{body, Body, Request};
+
+const SIMPLE_WAGI_MODULE: &str = "tests/wagi/target/wasm32-wasi/release/simple-wagi.wasm";
+
+#[tokio::test]
+async fn test_parse_cgi_response() {
+    let res =
+        parse_cgi_response(b"Content-Type: text/plain\r\nX-A: 1\r\nX-A: 2\r\n\r\nhello").unwrap();
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.headers()["content-type"], "text/plain");
+    assert_eq!(res.headers().get_all("x-a").iter().count(), 2);
+    let body = body::to_bytes(res.into_body()).await.unwrap();
+    assert_eq!(&body[..], b"hello");
+
+    let res = parse_cgi_response(b"Status: 404 Not Found\n\n").unwrap();
+    assert_eq!(res.status(), 404);
+    assert!(!res.headers().contains_key("status"));
+
+    let res = parse_cgi_response(b"Location: /new\n\n").unwrap();
+    assert_eq!(res.status(), 302);
+
+    assert!(parse_cgi_response(b"no header section").is_err());
+    assert!(parse_cgi_response(b"Status: teapot\n\n").is_err());
+}
+
+#[tokio::test]
+async fn test_wagi_module() {
+    let module = ModuleSource::File(SIMPLE_WAGI_MODULE.to_string());
+    let e = WagiEngine::build(&module, &Config::default()).unwrap();
+    let router = Router::new().route("/app/*".parse::<RoutePattern>().unwrap(), e);
+
+    let req = Request::builder()
+        .method("POST")
+        .uri("/app/echo?a=1")
+        .body(Body::from("ping"))
+        .unwrap();
+    let res = router.execute(req).await.unwrap();
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.headers()["x-query"], "a=1");
+    assert_eq!(res.headers()["x-method"], "POST");
+    let body = body::to_bytes(res.into_body()).await.unwrap();
+    assert_eq!(&body[..], b"ping");
+
+    let req = Request::builder()
+        .uri("/app/missing")
+        .body(Body::empty())
+        .unwrap();
+    assert_eq!(router.execute(req).await.unwrap().status(), 404);
+
+    let req = Request::builder()
+        .uri("/app/old")
+        .body(Body::empty())
+        .unwrap();
+    let res = router.execute(req).await.unwrap();
+    assert_eq!(res.status(), 302);
+    assert_eq!(res.headers()["location"], "/new");
+}
+
+#[test]
+fn test_split_path() {
+    let pattern: RoutePattern = "/app/*".parse().unwrap();
+    assert_eq!(
+        pattern.split_path("/app/a/b"),
+        ("/app".to_string(), "/a/b".to_string())
+    );
+
+    let pattern: RoutePattern = "/*".parse().unwrap();
+    assert_eq!(pattern.split_path("/a"), ("".to_string(), "/a".to_string()));
+}
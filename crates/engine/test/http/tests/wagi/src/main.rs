@@ -0,0 +1,28 @@
+use std::io::{self, Read};
+
+fn main() {
+    let path_info = std::env::var("PATH_INFO").unwrap_or_default();
+    if path_info == "/missing" {
+        println!("Status: 404 Not Found");
+        println!();
+        return;
+    }
+    if path_info == "/old" {
+        println!("Location: /new");
+        println!();
+        return;
+    }
+
+    let mut body = Vec::new();
+    io::stdin().read_to_end(&mut body).unwrap();
+
+    // Echo the request back, to test the CGI variables and stdin.
+    println!("Content-Type: text/plain");
+    println!(
+        "X-Query: {}",
+        std::env::var("QUERY_STRING").unwrap_or_default()
+    );
+    println!("X-Method: {}", std::env::var("REQUEST_METHOD").unwrap());
+    println!();
+    print!("{}", String::from_utf8_lossy(&body));
+}
@@ -1,11 +1,18 @@
 use anyhow::Error;
 use async_trait::async_trait;
 use deislabs_ping_v01::{DeislabsPingV01, DeislabsPingV01Data};
+use glass_engine::{
+    publish::Message, Config, ExecutionError, Interface, InterfaceRegistry, ModuleSource,
+    Publisher, WasiExecutionContextBuilder,
+};
 use std::{
     sync::Arc,
     time::{Duration, Instant},
 };
-use tokio::time;
+use tokio::{
+    sync::broadcast::{self, error::RecvError},
+    time,
+};
 
 witx_bindgen_wasmtime::export!("crates/engine/test/ping/deislabs_ping_v01.witx");
 
@@ -16,22 +23,112 @@ pub trait Ping: Clone + Send + Sync + 'static {
 
 pub struct TimerTrigger {
     pub interval: Duration,
+    /// Deadline for a single tick. Ticks that run past it are recorded as
+    /// timed out, and the trigger keeps firing.
+    pub timeout: Option<Duration>,
 }
 
 impl TimerTrigger {
     pub async fn run(&self, runtime: impl Ping) -> Result<(), Error> {
         let mut interval = time::interval(self.interval);
+        let mut timed_out = 0u64;
         loop {
             interval.tick().await;
-            let res = runtime
-                .execute(format!(
-                    "{}",
-                    chrono::Local::now().format("%Y-%m-%d][%H:%M:%S")
-                ))
-                .await?;
-
-            log::info!("{}\n", res);
+            let input = format!("{}", chrono::Local::now().format("%Y-%m-%d][%H:%M:%S"));
+
+            match self.tick(runtime.clone(), input).await {
+                Ok(Some(res)) => log::info!("{}\n", res),
+                Ok(None) => {
+                    timed_out += 1;
+                    log::warn!("Tick timed out ({} timed out ticks so far)", timed_out);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Execute a single tick, returning `None` if it timed out.
+    pub async fn tick(&self, runtime: impl Ping, input: String) -> Result<Option<String>, Error> {
+        execute(runtime, input, self.timeout).await
+    }
+}
+
+/// Executes the engine with the payload of every message guests publish to
+/// `topic`, through a backend delivering messages in the same process.
+pub struct MessageTrigger {
+    topic: String,
+    timeout: Option<Duration>,
+    messages: broadcast::Receiver<Arc<Message>>,
+}
+
+impl MessageTrigger {
+    /// Subscribe to the messages published to `topic` from now on. Executions
+    /// that run past `timeout` are recorded as timed out.
+    pub fn subscribe(
+        publisher: &dyn Publisher,
+        topic: &str,
+        timeout: Option<Duration>,
+    ) -> Result<Self, Error> {
+        let messages = publisher.subscribe().ok_or_else(|| {
+            anyhow::anyhow!("the publish backend does not deliver messages to triggers")
+        })?;
+
+        Ok(Self {
+            topic: topic.to_string(),
+            timeout,
+            messages,
+        })
+    }
+
+    /// Execute the engine for each message, until the publisher is dropped.
+    pub async fn run(mut self, runtime: impl Ping) -> Result<(), Error> {
+        loop {
+            let message = match self.messages.recv().await {
+                Ok(message) => message,
+                Err(RecvError::Lagged(skipped)) => {
+                    log::warn!("Skipped {} messages the trigger fell behind on", skipped);
+                    continue;
+                }
+                Err(RecvError::Closed) => return Ok(()),
+            };
+            if message.topic != self.topic {
+                continue;
+            }
+
+            let input = String::from_utf8_lossy(&message.payload).into_owned();
+            match execute(runtime.clone(), input, self.timeout).await? {
+                Some(res) => log::info!("{}\n", res),
+                None => log::warn!("Message to '{}' timed out", self.topic),
+            }
+        }
+    }
+}
+
+/// Execute the engine, returning `None` if it did not complete within `timeout`.
+async fn execute(
+    runtime: impl Ping,
+    input: String,
+    timeout: Option<Duration>,
+) -> Result<Option<String>, Error> {
+    let res = match timeout {
+        // The engine runs the guest synchronously, so it is spawned on its
+        // own task to keep the deadline observable while the guest is blocked.
+        Some(timeout) => {
+            let exec = tokio::spawn(async move { runtime.execute(input).await });
+            match time::timeout(timeout, exec).await {
+                Ok(res) => res?,
+                Err(_) => return Ok(None),
+            }
         }
+        None => runtime.execute(input).await,
+    };
+
+    match res {
+        Ok(res) => Ok(Some(res)),
+        Err(e) => match e.downcast_ref::<ExecutionError>() {
+            Some(ExecutionError::Timeout { .. }) => Ok(None),
+            _ => Err(e),
+        },
     }
 }
 
@@ -40,6 +137,33 @@ type WasiExecutionContext = glass_engine::WasiExecutionContext<DeislabsPingV01Da
 #[derive(Clone)]
 pub struct PingEngine(pub Arc<WasiExecutionContext>);
 
+impl PingEngine {
+    /// Name of the interface implemented by modules this engine executes.
+    pub const INTERFACE: &'static str = "deislabs_ping_v01";
+    /// Functions modules must export to implement the interface.
+    pub const EXPORTS: &'static [&'static str] = &["ping"];
+
+    /// Build an engine executing `module`, with all host imports available.
+    pub fn build(module: &ModuleSource, config: &Config) -> Result<Self, Error> {
+        let mut builder = WasiExecutionContextBuilder::new(config)?;
+        builder.add_all()?;
+
+        Ok(Self(Arc::new(builder.build_source(module)?)))
+    }
+}
+
+/// The ping interfaces supported by glass.
+pub fn registry() -> InterfaceRegistry<PingEngine> {
+    let mut registry = InterfaceRegistry::new();
+    registry.register(Interface {
+        name: PingEngine::INTERFACE,
+        exports: PingEngine::EXPORTS,
+        build: PingEngine::build,
+    });
+
+    registry
+}
+
 #[async_trait]
 impl Ping for PingEngine {
     async fn execute(&self, input: String) -> Result<String, Error> {
@@ -50,7 +174,16 @@ impl Ping for PingEngine {
             host.runtime_data.as_mut().unwrap()
         })?;
 
-        let res = pr.ping(&mut store, input.as_str())?;
+        let res = self
+            .0
+            .call(&mut store, |store| Ok(pr.ping(store, input.as_str())?))
+            .map_err(|e| {
+                match e.downcast_ref::<ExecutionError>() {
+                    Some(ExecutionError::Timeout { .. }) | None => {}
+                    Some(limit) => log::error!("Ping execution stopped: {}", limit),
+                }
+                e
+            })?;
 
         log::info!("Total execution time: {:?}", start.elapsed());
         Ok(res)
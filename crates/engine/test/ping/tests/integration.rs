@@ -1,6 +1,14 @@
-use glass_engine::WasiExecutionContextBuilder;
-use glass_ping::{Ping, PingEngine};
-use std::sync::Arc;
+use anyhow::Error;
+use async_trait::async_trait;
+use glass_engine::{
+    publish::{ChannelPublisher, Message},
+    Config, ExecutionError, ModuleSource, PoolingConfig, Publisher, WasiExecutionContextBuilder,
+};
+use glass_ping::{MessageTrigger, Ping, PingEngine, TimerTrigger};
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 
 const SIMPLE_C_MODULE: &str = "tests/c/ctest.wasm";
 
@@ -13,6 +21,207 @@ async fn test_c_ping() {
     test_example(SIMPLE_C_MODULE, input, exp).await;
 }
 
+#[tokio::test]
+async fn test_c_ping_out_of_fuel() {
+    let mut config = Config::default();
+    config.max_fuel = Some(1);
+
+    let mut builder = WasiExecutionContextBuilder::new(&config).unwrap();
+    builder.add_all().unwrap();
+    let pe = PingEngine(Arc::new(builder.build(SIMPLE_C_MODULE).unwrap()));
+
+    let err = pe.execute("ping".to_string()).await.unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<ExecutionError>(),
+        Some(&ExecutionError::OutOfFuel { budget: 1 })
+    );
+}
+
+#[tokio::test]
+async fn test_c_ping_memory_limit_exceeded() {
+    let mut config = Config::default();
+    config.resource_limits.max_memory_bytes = Some(1);
+
+    let mut builder = WasiExecutionContextBuilder::new(&config).unwrap();
+    builder.add_all().unwrap();
+    let pe = PingEngine(Arc::new(builder.build(SIMPLE_C_MODULE).unwrap()));
+
+    let err = pe.execute("ping".to_string()).await.unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<ExecutionError>(),
+        Some(ExecutionError::MemoryLimitExceeded { limit: 1, .. })
+    ));
+}
+
+#[test]
+fn test_c_ping_instance_and_table_limits_exceeded() {
+    let mut config = Config::default();
+    config.resource_limits.max_instances = Some(0);
+    let mut builder = WasiExecutionContextBuilder::<()>::new(&config).unwrap();
+    builder.add_all().unwrap();
+    let err = builder
+        .build(SIMPLE_C_MODULE)
+        .unwrap()
+        .prepare_exec(None)
+        .unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<ExecutionError>(),
+        Some(&ExecutionError::InstanceLimitExceeded { limit: 0 })
+    );
+
+    // The module has a table for indirect calls.
+    let mut config = Config::default();
+    config.resource_limits.max_tables = Some(0);
+    let mut builder = WasiExecutionContextBuilder::<()>::new(&config).unwrap();
+    builder.add_all().unwrap();
+    let err = builder
+        .build(SIMPLE_C_MODULE)
+        .unwrap()
+        .prepare_exec(None)
+        .unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<ExecutionError>(),
+        Some(&ExecutionError::TableCountExceeded { limit: 0 })
+    );
+}
+
+#[test]
+fn test_c_ping_pool_exhausted() {
+    let mut config = Config::default();
+    config.pooling = Some(PoolingConfig {
+        max_instances: 1,
+        ..PoolingConfig::default()
+    });
+
+    let mut builder = WasiExecutionContextBuilder::<()>::new(&config).unwrap();
+    builder.add_all().unwrap();
+    let ctx = builder.build(SIMPLE_C_MODULE).unwrap();
+
+    let _held = ctx.prepare_exec(None).unwrap();
+    let err = ctx.prepare_exec(None).unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<ExecutionError>(),
+        Some(&ExecutionError::PoolExhausted { max_instances: 1 })
+    );
+}
+
+/// A runtime standing in for a guest that runs past the deadline of a tick,
+/// either blocking the trigger or interrupted by the engine.
+#[derive(Clone)]
+struct Late {
+    interrupted: bool,
+}
+
+#[async_trait]
+impl Ping for Late {
+    async fn execute(&self, _: String) -> Result<String, Error> {
+        if self.interrupted {
+            let timeout = Duration::from_millis(100);
+            return Err(anyhow::anyhow!("interrupted").context(ExecutionError::Timeout { timeout }));
+        }
+
+        std::thread::sleep(Duration::from_secs(1));
+        Ok("too late".to_string())
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_tick_timeout() {
+    let trigger = TimerTrigger {
+        interval: Duration::from_secs(1),
+        timeout: Some(Duration::from_millis(100)),
+    };
+
+    for interrupted in [false, true] {
+        let res = trigger
+            .tick(Late { interrupted }, "ping".to_string())
+            .await
+            .unwrap();
+        assert_eq!(res, None);
+    }
+
+    let mut config = Config::default();
+    config.timeout = trigger.timeout;
+    let mut builder = WasiExecutionContextBuilder::new(&config).unwrap();
+    builder.add_all().unwrap();
+    let pe = PingEngine(Arc::new(builder.build(SIMPLE_C_MODULE).unwrap()));
+    let res = trigger.tick(pe, "ping".to_string()).await.unwrap();
+    assert_eq!(res.as_deref(), Some("PONG: ping"));
+}
+
+/// A runtime recording its inputs.
+#[derive(Clone, Default)]
+struct Recorder(Arc<Mutex<Vec<String>>>);
+
+#[async_trait]
+impl Ping for Recorder {
+    async fn execute(&self, input: String) -> Result<String, Error> {
+        self.0.lock().unwrap().push(input.clone());
+        Ok(input)
+    }
+}
+
+#[tokio::test]
+async fn test_message_trigger() {
+    let publisher = ChannelPublisher::default();
+    let trigger = MessageTrigger::subscribe(&publisher, "pings", None).unwrap();
+
+    for (topic, payload) in [
+        ("pings", "first"),
+        ("other", "ignored"),
+        ("pings", "second"),
+    ] {
+        let message = Message {
+            topic: topic.to_string(),
+            payload: payload.as_bytes().to_vec(),
+            attributes: Vec::new(),
+        };
+        publisher.publish(&message).unwrap();
+    }
+    // The trigger stops once the publisher is dropped.
+    drop(publisher);
+
+    let recorder = Recorder::default();
+    trigger.run(recorder.clone()).await.unwrap();
+    assert_eq!(*recorder.0.lock().unwrap(), vec!["first", "second"]);
+}
+
+/// A publisher delivering messages outside of the process.
+struct Remote;
+
+impl Publisher for Remote {
+    fn publish(&self, _: &Message) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_message_trigger_requires_subscription() {
+    assert!(MessageTrigger::subscribe(&Remote, "pings", None).is_err());
+}
+
+#[tokio::test]
+async fn test_auto_interface() {
+    let module = ModuleSource::File(SIMPLE_C_MODULE.to_string());
+    let pe = glass_ping::registry()
+        .build("auto", &module, &Config::default())
+        .unwrap();
+
+    let res = pe.execute("auto".to_string()).await.unwrap();
+    assert_eq!(res, "PONG: auto");
+}
+
+#[test]
+fn test_unknown_interface() {
+    let module = ModuleSource::File(SIMPLE_C_MODULE.to_string());
+    let err = glass_ping::registry()
+        .select("deislabs_ping_v99", &module, &Config::default())
+        .err()
+        .unwrap();
+
+    assert!(err.to_string().contains("deislabs_ping_v01"));
+}
+
 async fn test_example(entrypoint: &str, input: String, exp: String) {
     let pe = PingEngine(Arc::new(
         WasiExecutionContextBuilder::build_default(entrypoint).unwrap(),
@@ -0,0 +1,135 @@
+use glass_engine::source::bindle;
+use hyper::{
+    service::{make_service_fn, service_fn},
+    Body, Response, Server, StatusCode,
+};
+use sha2::{Digest, Sha256};
+use std::{convert::Infallible, net::SocketAddr};
+
+const BINDLE_ID: &str = "glass/example/0.1.0";
+const MODULE: &[u8] = b"(module)";
+const ASSET: &[u8] = b"Marcus Aurelius";
+
+#[tokio::test]
+async fn test_fetch_bindle() {
+    let server = serve(invoice(&digest(MODULE))).await;
+    let cache = tempfile::tempdir().unwrap();
+
+    let module = bindle::fetch(&server, BINDLE_ID, cache.path())
+        .await
+        .unwrap();
+
+    assert_eq!(std::fs::read(&module.entrypoint).unwrap(), MODULE);
+    let assets = module.assets.unwrap();
+    assert_eq!(
+        std::fs::read(assets.join("static/emperor.txt")).unwrap(),
+        ASSET
+    );
+}
+
+#[tokio::test]
+async fn test_fetch_bindle_digest_mismatch() {
+    let server = serve(invoice(&digest(b"(module $other)"))).await;
+    let cache = tempfile::tempdir().unwrap();
+
+    let err = bindle::fetch(&server, BINDLE_ID, cache.path())
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("digest mismatch"));
+}
+
+#[tokio::test]
+async fn test_fetch_bindle_invalid_digest() {
+    let server = serve(invoice("../../escape")).await;
+    let cache = tempfile::tempdir().unwrap();
+
+    let err = bindle::fetch(&server, BINDLE_ID, cache.path())
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("invalid digest"));
+}
+
+#[tokio::test]
+async fn test_fetch_bindle_corrupted_cache() {
+    let server = serve(invoice(&digest(MODULE))).await;
+    let cache = tempfile::tempdir().unwrap();
+
+    let module = bindle::fetch(&server, BINDLE_ID, cache.path())
+        .await
+        .unwrap();
+    std::fs::write(&module.entrypoint, b"(module $corrupted)").unwrap();
+
+    // The corrupted parcel is fetched again rather than used.
+    let module = bindle::fetch(&server, BINDLE_ID, cache.path())
+        .await
+        .unwrap();
+    assert_eq!(std::fs::read(&module.entrypoint).unwrap(), MODULE);
+}
+
+/// Start a server standing in for Bindle, serving a single invoice
+/// and its parcels, and return its URL.
+async fn serve(invoice: String) -> String {
+    let make_svc = make_service_fn(move |_| {
+        let invoice = invoice.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |req| {
+                let path = req.uri().path().to_string();
+                let res = if path.ends_with(&format!("@{}", digest(ASSET))) {
+                    Response::new(Body::from(ASSET))
+                } else if path.contains('@') {
+                    Response::new(Body::from(MODULE))
+                } else if path.ends_with(BINDLE_ID) {
+                    Response::new(Body::from(invoice.clone()))
+                } else {
+                    Response::builder()
+                        .status(StatusCode::NOT_FOUND)
+                        .body(Body::empty())
+                        .unwrap()
+                };
+                async move { Ok::<_, Infallible>(res) }
+            }))
+        }
+    });
+
+    let server = Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).serve(make_svc);
+    let url = format!("http://{}/v1", server.local_addr());
+    tokio::spawn(server);
+
+    url
+}
+
+fn invoice(module_sha: &str) -> String {
+    format!(
+        r#"
+bindleVersion = "1.0.0"
+
+[bindle]
+name = "glass/example"
+version = "0.1.0"
+
+[[parcel]]
+[parcel.label]
+sha256 = "{}"
+mediaType = "application/wasm"
+name = "example.wasm"
+size = {}
+
+[[parcel]]
+[parcel.label]
+sha256 = "{}"
+mediaType = "text/plain"
+name = "static/emperor.txt"
+size = {}
+[parcel.label.feature.glass]
+file = "true"
+"#,
+        module_sha,
+        MODULE.len(),
+        digest(ASSET),
+        ASSET.len()
+    )
+}
+
+fn digest(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
@@ -0,0 +1,77 @@
+use glass_engine::{Config, WasiExecutionContextBuilder};
+use std::{fs, path::PathBuf, time::SystemTime};
+
+const MODULE: &str = r#"(module (func (export "run")))"#;
+
+/// Build the module using `config`, then run it.
+fn build(config: &Config) {
+    let ctx = WasiExecutionContextBuilder::<()>::new(config)
+        .unwrap()
+        .build_from_bytes("cached", MODULE.as_bytes())
+        .unwrap();
+    let (mut store, instance) = ctx.prepare_exec(None).unwrap();
+    let run = instance
+        .get_typed_func::<(), (), _>(&mut store, "run")
+        .unwrap();
+    ctx.call(&mut store, |store| Ok(run.call(store, ())?))
+        .unwrap();
+}
+
+/// The compiled modules in the cache, and when they were written.
+fn entries(config: &Config) -> Vec<(PathBuf, SystemTime)> {
+    let dir = config.cache_dir.as_ref().unwrap();
+    let mut entries: Vec<_> = fs::read_dir(dir)
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .map(|path| {
+            let modified = fs::metadata(&path).unwrap().modified().unwrap();
+            (path, modified)
+        })
+        .collect();
+    entries.sort();
+    entries
+}
+
+#[test]
+fn test_cache_hit_and_miss() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = Config {
+        cache_dir: Some(dir.path().to_path_buf()),
+        ..Config::default()
+    };
+
+    build(&config);
+    let cached = entries(&config);
+    assert_eq!(cached.len(), 1);
+    assert_eq!(cached[0].0.extension().unwrap(), "cwasm");
+
+    // The module is loaded from the cache, without writing it again.
+    build(&config);
+    assert_eq!(entries(&config), cached);
+}
+
+#[test]
+fn test_cache_invalidation() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut config = Config {
+        cache_dir: Some(dir.path().to_path_buf()),
+        ..Config::default()
+    };
+    build(&config);
+    let before = entries(&config);
+
+    // Changing the code generation settings adds another entry, keeping the
+    // one that may still be used by other processes.
+    config.max_fuel = Some(1_000_000);
+    build(&config);
+    let after = entries(&config);
+    assert_eq!(after.len(), 2);
+    assert!(after.contains(&before[0]));
+    let (entry, _) = after.iter().find(|e| **e != before[0]).unwrap();
+
+    // Unusable entries are compiled again.
+    fs::write(entry, b"not a module").unwrap();
+    build(&config);
+    assert_ne!(fs::read(entry).unwrap(), b"not a module");
+    assert_eq!(entries(&config).len(), 2);
+}
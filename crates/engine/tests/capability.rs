@@ -0,0 +1,91 @@
+use anyhow::Error;
+use glass_engine::{Context, HostCapability, Invocation, WasiExecutionContextBuilder};
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+use wasi_common::WasiCtx;
+use wasmtime::{Caller, Linker};
+
+const MODULE: &str = r#"
+(module
+    (import "counter" "bump" (func $bump))
+    (func (export "run")
+        call $bump
+        call $bump))
+"#;
+
+/// State of the counter in a store.
+struct Count(u32);
+
+/// A capability counting the calls to its import in each store.
+#[derive(Default)]
+struct Counter {
+    stores: Arc<AtomicUsize>,
+}
+
+impl HostCapability<()> for Counter {
+    fn add_to_linker(&self, linker: &mut Linker<Context<()>>) -> Result<(), Error> {
+        linker.func_wrap("counter", "bump", |mut caller: Caller<'_, Context<()>>| {
+            caller.data_mut().get_mut::<Count>().unwrap().0 += 1;
+        })?;
+        Ok(())
+    }
+
+    fn init_store(&self, ctx: &mut Context<()>) -> Result<(), Error> {
+        self.stores.fetch_add(1, Ordering::SeqCst);
+        ctx.insert(Count(0));
+        Ok(())
+    }
+}
+
+#[test]
+fn test_custom_capability() {
+    let counter = Counter::default();
+    let stores = counter.stores.clone();
+
+    let mut builder = WasiExecutionContextBuilder::<()>::default().unwrap();
+    builder.with_capability(counter).unwrap();
+    let ctx = builder
+        .build_from_bytes("counter", MODULE.as_bytes())
+        .unwrap();
+
+    for _ in 0..2 {
+        let (mut store, instance) = ctx.prepare_exec(None).unwrap();
+        let run = instance
+            .get_typed_func::<(), (), _>(&mut store, "run")
+            .unwrap();
+        ctx.call(&mut store, |store| Ok(run.call(store, ())?))
+            .unwrap();
+
+        assert_eq!(store.data().get::<Count>().unwrap().0, 2);
+        assert_eq!(store.data().module(), "counter");
+    }
+    assert_eq!(stores.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn test_missing_capability() {
+    // Without the capability, the module's import cannot be satisfied.
+    let mut builder = WasiExecutionContextBuilder::<()>::default().unwrap();
+    builder.add_wasi().unwrap();
+
+    assert!(builder
+        .build_from_bytes("counter", MODULE.as_bytes())
+        .is_err());
+}
+
+#[test]
+fn test_wasi_state_in_extensions() {
+    let mut builder = WasiExecutionContextBuilder::<()>::default().unwrap();
+    builder.add_wasi().unwrap();
+    builder.with_capability(Counter::default()).unwrap();
+    let ctx = builder
+        .build_from_bytes("counter", MODULE.as_bytes())
+        .unwrap();
+
+    // The WASI capability consumes the invocation to create its context.
+    let (store, _) = ctx.prepare_exec(None).unwrap();
+    assert!(store.data().get::<WasiCtx>().is_some());
+    assert!(store.data().get::<Invocation>().is_none());
+}
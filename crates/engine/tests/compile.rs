@@ -0,0 +1,86 @@
+use glass_engine::{is_precompiled, precompile_module, Config, WasiExecutionContextBuilder};
+use std::path::Path;
+
+const MODULE: &str = r#"(module (func (export "answer") (result i32) i32.const 42))"#;
+
+/// Write a precompiled artifact to `dir`, returning its path.
+fn write_artifact(dir: &Path, config: &Config) -> String {
+    let artifact = precompile_module(config, MODULE.as_bytes()).unwrap();
+    assert!(is_precompiled(&artifact));
+    assert!(!is_precompiled(MODULE.as_bytes()));
+
+    let path = dir.join("answer.cwasm");
+    std::fs::write(&path, artifact).unwrap();
+    path.to_string_lossy().into_owned()
+}
+
+#[test]
+fn test_precompile_and_load() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = Config {
+        allow_precompiled: true,
+        ..Config::default()
+    };
+    let path = write_artifact(dir.path(), &config);
+
+    let ctx = WasiExecutionContextBuilder::<()>::new(&config)
+        .unwrap()
+        .build(&path)
+        .unwrap();
+    let (mut store, instance) = ctx.prepare_exec(None).unwrap();
+    let answer = instance
+        .get_typed_func::<(), i32, _>(&mut store, "answer")
+        .unwrap();
+    assert_eq!(answer.call(&mut store, ()).unwrap(), 42);
+}
+
+#[test]
+fn test_load_with_different_config() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = Config {
+        max_fuel: Some(1_000_000),
+        ..Config::default()
+    };
+    let path = write_artifact(dir.path(), &config);
+
+    let config = Config {
+        allow_precompiled: true,
+        ..Config::default()
+    };
+    let err = WasiExecutionContextBuilder::<()>::new(&config)
+        .unwrap()
+        .build(&path)
+        .err()
+        .unwrap();
+    assert!(err.to_string().contains("different configuration"));
+}
+
+#[test]
+fn test_precompiled_requires_opt_in() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = Config::default();
+    let path = write_artifact(dir.path(), &config);
+
+    let err = WasiExecutionContextBuilder::<()>::new(&config)
+        .unwrap()
+        .build(&path)
+        .err()
+        .unwrap();
+    assert!(err.to_string().contains("explicitly allowed"));
+}
+
+#[test]
+fn test_precompiled_only_from_local_files() {
+    let config = Config {
+        allow_precompiled: true,
+        ..Config::default()
+    };
+    let artifact = precompile_module(&config, MODULE.as_bytes()).unwrap();
+
+    let err = WasiExecutionContextBuilder::<()>::new(&config)
+        .unwrap()
+        .build_from_bytes("oci://registry/answer:v1", &artifact)
+        .err()
+        .unwrap();
+    assert!(err.to_string().contains("local file"));
+}
@@ -0,0 +1,121 @@
+use glass_engine::{
+    kv::{MemoryStore, SqliteStore},
+    KvBackend, KvStore,
+};
+use std::{path::PathBuf, thread, time::Duration};
+
+/// Exercise the operations every backend must support.
+fn check_store(store: &dyn KvStore) {
+    assert_eq!(store.get("a", "missing").unwrap(), None);
+
+    store.set("a", "key", b"one", None).unwrap();
+    assert_eq!(store.get("a", "key").unwrap(), Some(b"one".to_vec()));
+    // Namespaces are isolated.
+    assert_eq!(store.get("b", "key").unwrap(), None);
+
+    store.set("a", "key-2", b"two", None).unwrap();
+    store.set("a", "other", b"three", None).unwrap();
+    store.set("b", "key-3", b"four", None).unwrap();
+    assert_eq!(store.list_keys("a", "key").unwrap(), vec!["key", "key-2"]);
+    assert_eq!(
+        store.list_keys("a", "").unwrap(),
+        vec!["key", "key-2", "other"]
+    );
+    assert_eq!(store.list_keys("a", "*").unwrap(), Vec::<String>::new());
+
+    assert!(store.delete("a", "key-2").unwrap());
+    assert!(!store.delete("a", "key-2").unwrap());
+    assert_eq!(store.get("a", "key-2").unwrap(), None);
+
+    assert!(!store
+        .compare_and_swap("a", "key", Some(b"wrong"), b"new", None)
+        .unwrap());
+    assert!(!store
+        .compare_and_swap("a", "key", None, b"new", None)
+        .unwrap());
+    assert!(store
+        .compare_and_swap("a", "key", Some(b"one"), b"new", None)
+        .unwrap());
+    assert_eq!(store.get("a", "key").unwrap(), Some(b"new".to_vec()));
+    assert!(store
+        .compare_and_swap("a", "created", None, b"value", None)
+        .unwrap());
+
+    store
+        .set("a", "expiring", b"soon", Some(Duration::from_millis(50)))
+        .unwrap();
+    // TTLs too long to represent never expire.
+    let forever = Some(Duration::from_millis(u64::MAX));
+    store.set("a", "forever", b"value", forever).unwrap();
+    assert!(store
+        .compare_and_swap("a", "forever-cas", None, b"value", forever)
+        .unwrap());
+    assert_eq!(store.get("a", "expiring").unwrap(), Some(b"soon".to_vec()));
+    thread::sleep(Duration::from_millis(100));
+    assert_eq!(store.get("a", "expiring").unwrap(), None);
+    assert_eq!(store.get("a", "forever").unwrap(), Some(b"value".to_vec()));
+    assert_eq!(
+        store.get("a", "forever-cas").unwrap(),
+        Some(b"value".to_vec())
+    );
+    assert!(!store
+        .list_keys("a", "")
+        .unwrap()
+        .contains(&"expiring".to_string()));
+    // An expired key is not set anymore.
+    assert!(store
+        .compare_and_swap("a", "expiring", None, b"again", None)
+        .unwrap());
+}
+
+#[test]
+fn test_memory_store() {
+    check_store(&MemoryStore::default());
+}
+
+#[test]
+fn test_sqlite_store() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("kv.db");
+
+    check_store(&SqliteStore::open(&path).unwrap());
+
+    // Values persist when the database is opened again.
+    let store = SqliteStore::open(&path).unwrap();
+    assert_eq!(store.get("a", "created").unwrap(), Some(b"value".to_vec()));
+}
+
+/// Run with a local `redis-server`, setting `GLASS_TEST_REDIS_URL` if it
+/// does not listen on the default port.
+#[cfg(feature = "redis")]
+#[test]
+#[ignore]
+fn test_redis_store() {
+    let url =
+        std::env::var("GLASS_TEST_REDIS_URL").unwrap_or_else(|_| "redis://127.0.0.1/".to_string());
+    let store = glass_engine::kv::RedisStore::open(&url).unwrap();
+
+    // Start from a clean state, in case a previous run failed.
+    for namespace in ["a", "b"] {
+        for key in store.list_keys(namespace, "").unwrap() {
+            store.delete(namespace, &key).unwrap();
+        }
+    }
+
+    check_store(&store);
+}
+
+#[test]
+fn test_parse_kv_backend() {
+    assert_eq!("memory".parse::<KvBackend>().unwrap(), KvBackend::Memory);
+    assert_eq!(
+        "sqlite:data/kv.db".parse::<KvBackend>().unwrap(),
+        KvBackend::Sqlite(PathBuf::from("data/kv.db"))
+    );
+    assert_eq!(
+        "redis://localhost:6379".parse::<KvBackend>().unwrap(),
+        KvBackend::Redis("redis://localhost:6379".to_string())
+    );
+
+    assert!("etcd://localhost".parse::<KvBackend>().is_err());
+}
@@ -0,0 +1,165 @@
+use glass_engine::source::oci::{self, Reference};
+use hyper::{
+    header,
+    service::{make_service_fn, service_fn},
+    Body, Response, Server, StatusCode,
+};
+use sha2::{Digest, Sha256};
+use std::{convert::Infallible, net::TcpListener};
+
+const MODULE: &[u8] = b"(module)";
+const TOKEN: &str = "anonymous";
+
+#[test]
+fn test_parse_reference() {
+    let r = Reference::parse("oci://localhost:5000/glass/example:v1").unwrap();
+    assert_eq!(r.registry, "localhost:5000");
+    assert_eq!(r.repository, "glass/example");
+    assert_eq!(r.reference, "v1");
+
+    let r = Reference::parse("ghcr.io/glass/example").unwrap();
+    assert_eq!(r.reference, "latest");
+
+    let r = Reference::parse("ghcr.io/glass/example@sha256:abc").unwrap();
+    assert_eq!(r.repository, "glass/example");
+    assert_eq!(r.reference, "sha256:abc");
+
+    assert!(Reference::parse("oci://example").is_err());
+}
+
+#[tokio::test]
+async fn test_pull() {
+    let registry = serve(format!("sha256:{:x}", Sha256::digest(MODULE))).await;
+    let cache = tempfile::tempdir().unwrap();
+
+    let reference = format!("oci://{}/glass/example:v1", registry);
+    let bytes = oci::pull(&reference, cache.path(), true).await.unwrap();
+    assert_eq!(bytes, MODULE);
+}
+
+#[tokio::test]
+async fn test_pull_digest_mismatch() {
+    let registry = serve(format!("sha256:{:x}", Sha256::digest(b"(module $other)"))).await;
+    let cache = tempfile::tempdir().unwrap();
+
+    let reference = format!("oci://{}/glass/example:v1", registry);
+    let err = oci::pull(&reference, cache.path(), true).await.unwrap_err();
+    assert!(err.to_string().contains("digest mismatch"));
+}
+
+#[tokio::test]
+async fn test_pull_invalid_layer_digest() {
+    let registry = serve("sha256:../../escape".to_string()).await;
+    let cache = tempfile::tempdir().unwrap();
+
+    let reference = format!("oci://{}/glass/example:v1", registry);
+    let err = oci::pull(&reference, cache.path(), true).await.unwrap_err();
+    assert!(err.to_string().contains("invalid layer digest"));
+}
+
+#[tokio::test]
+async fn test_pull_corrupted_cache() {
+    let digest = format!("{:x}", Sha256::digest(MODULE));
+    let registry = serve(format!("sha256:{}", digest)).await;
+    let cache = tempfile::tempdir().unwrap();
+
+    let reference = format!("oci://{}/glass/example:v1", registry);
+    oci::pull(&reference, cache.path(), true).await.unwrap();
+    std::fs::write(cache.path().join(&digest), b"(module $corrupted)").unwrap();
+
+    // The corrupted layer is pulled again rather than used.
+    let bytes = oci::pull(&reference, cache.path(), true).await.unwrap();
+    assert_eq!(bytes, MODULE);
+    assert_eq!(std::fs::read(cache.path().join(&digest)).unwrap(), MODULE);
+}
+
+#[tokio::test]
+async fn test_pull_by_digest() {
+    let layer_digest = format!("sha256:{:x}", Sha256::digest(MODULE));
+    let registry = serve(layer_digest.clone()).await;
+    let cache = tempfile::tempdir().unwrap();
+
+    let manifest = format!("{:x}", Sha256::digest(manifest(&layer_digest)));
+    let reference = format!("oci://{}/glass/example@sha256:{}", registry, manifest);
+    let bytes = oci::pull(&reference, cache.path(), true).await.unwrap();
+    assert_eq!(bytes, MODULE);
+
+    let other = format!("{:x}", Sha256::digest(b"{}"));
+    let reference = format!("oci://{}/glass/example@sha256:{}", registry, other);
+    let err = oci::pull(&reference, cache.path(), true).await.unwrap_err();
+    assert!(err.to_string().contains("digest mismatch for manifest"));
+
+    let reference = format!("oci://{}/glass/example@sha256:abc", registry);
+    assert!(oci::pull(&reference, cache.path(), true).await.is_err());
+}
+
+/// Start a server standing in for an OCI registry, serving a single manifest
+/// and its WebAssembly layer, and return its address.
+///
+/// Like most registries, it requires an anonymous token whose scope contains
+/// a comma, to test the challenge is parsed correctly.
+async fn serve(layer_digest: String) -> String {
+    let manifest = manifest(&layer_digest);
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    let challenge = format!(
+        r#"Bearer realm="http://{}/token",service="registry",scope="repository:glass/example:pull,push""#,
+        addr
+    );
+
+    let make_svc = make_service_fn(move |_| {
+        let manifest = manifest.clone();
+        let challenge = challenge.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |req| {
+                let authorized = req
+                    .headers()
+                    .get(header::AUTHORIZATION)
+                    .map_or(false, |v| v == format!("Bearer {}", TOKEN).as_str());
+                let query = req.uri().query().unwrap_or_default();
+                let res = match req.uri().path() {
+                    "/token"
+                        if query.contains("service=registry")
+                            && query
+                                .contains("scope=repository%3Aglass%2Fexample%3Apull%2Cpush") =>
+                    {
+                        Response::new(Body::from(format!(r#"{{"token":"{}"}}"#, TOKEN)))
+                    }
+                    p if p.starts_with("/v2/") && !authorized => Response::builder()
+                        .status(StatusCode::UNAUTHORIZED)
+                        .header(header::WWW_AUTHENTICATE, challenge.as_str())
+                        .body(Body::empty())
+                        .unwrap(),
+                    p if p.starts_with("/v2/glass/example/manifests/") => {
+                        Response::new(Body::from(manifest.clone()))
+                    }
+                    p if p.starts_with("/v2/glass/example/blobs/") => {
+                        Response::new(Body::from(MODULE))
+                    }
+                    _ => Response::builder()
+                        .status(StatusCode::NOT_FOUND)
+                        .body(Body::empty())
+                        .unwrap(),
+                };
+                async move { Ok::<_, Infallible>(res) }
+            }))
+        }
+    });
+
+    tokio::spawn(Server::from_tcp(listener).unwrap().serve(make_svc));
+
+    addr
+}
+
+fn manifest(layer_digest: &str) -> String {
+    format!(
+        r#"{{
+            "schemaVersion": 2,
+            "config": {{ "mediaType": "application/vnd.wasm.config.v1+json", "digest": "sha256:00", "size": 0 }},
+            "layers": [{{ "mediaType": "application/vnd.wasm.content.layer.v1+wasm", "digest": "{}", "size": {} }}]
+        }}"#,
+        layer_digest,
+        MODULE.len()
+    )
+}
@@ -0,0 +1,154 @@
+use glass_engine::{
+    publish::{is_valid_topic, ChannelPublisher, FilePublisher, FileRecord, Message},
+    PublishBackend, Publisher,
+};
+use std::{fs, path::PathBuf};
+
+fn message(topic: &str, payload: &[u8]) -> Message {
+    Message {
+        topic: topic.to_string(),
+        payload: payload.to_vec(),
+        attributes: vec![("content-type".to_string(), "text/plain".to_string())],
+    }
+}
+
+#[test]
+fn test_file_publisher() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("messages.jsonl");
+
+    let publisher = FilePublisher::open(&path).unwrap();
+    publisher.publish(&message("orders", b"first")).unwrap();
+    publisher.publish(&message("orders", &[0, 159])).unwrap();
+    drop(publisher);
+    // Messages are appended to the existing file.
+    FilePublisher::open(&path)
+        .unwrap()
+        .publish(&message("audit", b"third"))
+        .unwrap();
+
+    let records: Vec<FileRecord> = fs::read_to_string(&path)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(records.len(), 3);
+    assert_eq!(records[0].topic, "orders");
+    assert_eq!(base64::decode(&records[0].payload).unwrap(), b"first");
+    assert_eq!(records[0].attributes["content-type"], "text/plain");
+    assert_eq!(base64::decode(&records[1].payload).unwrap(), [0, 159]);
+    assert_eq!(records[2].topic, "audit");
+}
+
+#[test]
+fn test_channel_publisher() {
+    let publisher = ChannelPublisher::default();
+    // Publishing without subscribers is not an error.
+    publisher.publish(&message("orders", b"lost")).unwrap();
+
+    let mut first = publisher.subscribe().unwrap();
+    let mut second = publisher.subscribe().unwrap();
+    publisher.publish(&message("orders", b"hello")).unwrap();
+
+    assert_eq!(*first.try_recv().unwrap(), message("orders", b"hello"));
+    assert_eq!(*second.try_recv().unwrap(), message("orders", b"hello"));
+    assert!(first.try_recv().is_err());
+}
+
+#[test]
+fn test_valid_topics() {
+    assert!(is_valid_topic("orders"));
+    assert!(is_valid_topic("orders.created"));
+    assert!(is_valid_topic("sensors/+/temperature"));
+
+    assert!(!is_valid_topic(""));
+    assert!(!is_valid_topic("two words"));
+    assert!(!is_valid_topic("line\nbreak"));
+}
+
+#[test]
+fn test_parse_publish_backend() {
+    assert_eq!(
+        "file:events.jsonl".parse::<PublishBackend>().unwrap(),
+        PublishBackend::File(PathBuf::from("events.jsonl"))
+    );
+    assert_eq!(
+        "channel".parse::<PublishBackend>().unwrap(),
+        PublishBackend::Channel
+    );
+    assert_eq!(
+        "nats://localhost:4222".parse::<PublishBackend>().unwrap(),
+        PublishBackend::Nats("nats://localhost:4222".to_string())
+    );
+    assert_eq!(
+        "mqtt://localhost".parse::<PublishBackend>().unwrap(),
+        PublishBackend::Mqtt("mqtt://localhost".to_string())
+    );
+
+    assert!("kafka://localhost".parse::<PublishBackend>().is_err());
+}
+
+/// Run with a local `nats-server`, setting `GLASS_TEST_NATS_URL` if it
+/// does not listen on the default port.
+#[cfg(feature = "nats")]
+#[test]
+#[ignore]
+fn test_nats_publisher() {
+    use std::time::Duration;
+
+    let url = std::env::var("GLASS_TEST_NATS_URL")
+        .unwrap_or_else(|_| "nats://127.0.0.1:4222".to_string());
+    let subscriber = nats::connect(&url)
+        .unwrap()
+        .subscribe("glass.test")
+        .unwrap();
+
+    let publisher = glass_engine::publish::NatsPublisher::connect(&url).unwrap();
+    publisher.publish(&message("glass.test", b"hello")).unwrap();
+
+    let received = subscriber.next_timeout(Duration::from_secs(5)).unwrap();
+    assert_eq!(received.data, b"hello");
+    let headers = received.headers.unwrap();
+    assert!(headers.get("content-type").is_some());
+}
+
+/// Run with a local MQTT broker such as `mosquitto`, setting
+/// `GLASS_TEST_MQTT_URL` if it does not listen on the default port.
+#[cfg(feature = "mqtt")]
+#[test]
+#[ignore]
+fn test_mqtt_publisher() {
+    use rumqttc::{Client, Event, MqttOptions, Packet, QoS};
+    use std::time::Duration;
+
+    let url = std::env::var("GLASS_TEST_MQTT_URL")
+        .unwrap_or_else(|_| "mqtt://127.0.0.1:1883".to_string());
+    let address = url.trim_start_matches("mqtt://");
+    let (host, port) = address.rsplit_once(':').unwrap_or((address, "1883"));
+
+    let options = MqttOptions::new("glass-test-subscriber", host, port.parse().unwrap());
+    let (mut client, mut connection) = Client::new(options, 10);
+    client.subscribe("glass/test", QoS::AtLeastOnce).unwrap();
+    // Wait for the subscription before publishing.
+    for event in connection.iter() {
+        if let Event::Incoming(Packet::SubAck(_)) = event.unwrap() {
+            break;
+        }
+    }
+
+    let publisher = glass_engine::publish::MqttPublisher::connect(&url).unwrap();
+    publisher.publish(&message("glass/test", b"hello")).unwrap();
+
+    let (sender, receiver) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        for event in connection.iter() {
+            if let Ok(Event::Incoming(Packet::Publish(publish))) = event {
+                let _ = sender.send(publish);
+                return;
+            }
+        }
+    });
+    let received = receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert_eq!(received.topic, "glass/test");
+    assert_eq!(&received.payload[..], b"hello");
+}
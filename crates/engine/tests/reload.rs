@@ -0,0 +1,29 @@
+use glass_engine::Reloadable;
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+#[test]
+fn test_reload_keeps_previous_engine_on_failure() {
+    let builds = Arc::new(AtomicUsize::new(0));
+    let counter = builds.clone();
+    let engine = Reloadable::new(move || {
+        let n = counter.fetch_add(1, Ordering::SeqCst);
+        anyhow::ensure!(n != 2, "compilation failed");
+        Ok(n)
+    })
+    .unwrap();
+    assert_eq!(engine.current(), 0);
+
+    let in_flight = engine.current();
+    assert!(engine.reload());
+    assert_eq!(engine.current(), 1);
+    assert_eq!(in_flight, 0);
+
+    assert!(!engine.reload());
+    assert_eq!(engine.current(), 1);
+
+    assert!(engine.reload());
+    assert_eq!(engine.current(), 3);
+}
@@ -0,0 +1,151 @@
+use glass_engine::{
+    runtime_config::{ConfigValues, REDACTED},
+    ConfigSource, RuntimeConfig,
+};
+use std::{
+    fs,
+    time::{Duration, Instant},
+};
+
+fn value(values: &ConfigValues, key: &str) -> Option<String> {
+    values.get(key).map(|v| v.value.clone())
+}
+
+#[test]
+fn test_layered_sources() {
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path();
+    let file = dir.join("config.toml");
+    fs::write(
+        &file,
+        "name = \"file\"\nport = 8080\n[db]\nurl = \"postgres://db\"\npassword = \"file\"\n",
+    )
+    .unwrap();
+    let secrets = dir.join("secrets");
+    fs::create_dir(&secrets).unwrap();
+    fs::write(secrets.join("db.password"), "hunter2\n").unwrap();
+    fs::write(secrets.join(".hidden"), "ignored").unwrap();
+    std::env::set_var("GLASS_TEST_LAYERS_NAME", "env");
+    std::env::set_var("GLASS_TEST_LAYERS_REGION", "west");
+    #[cfg(unix)]
+    std::env::set_var(
+        "GLASS_TEST_LAYERS_INVALID",
+        <std::ffi::OsStr as std::os::unix::ffi::OsStrExt>::from_bytes(b"\xff"),
+    );
+
+    let values = ConfigValues::load(&[
+        ConfigSource::Values(vec![("name".to_string(), "cli".to_string())]),
+        ConfigSource::File(file),
+        ConfigSource::SecretsDir(secrets),
+        ConfigSource::Env {
+            prefix: "GLASS_TEST_LAYERS_".to_string(),
+        },
+    ])
+    .unwrap();
+
+    assert_eq!(value(&values, "name").as_deref(), Some("cli"));
+    assert_eq!(value(&values, "port").as_deref(), Some("8080"));
+    assert_eq!(value(&values, "db.url").as_deref(), Some("postgres://db"));
+    // The file takes precedence over the secrets directory, but the value
+    // stays secret.
+    assert_eq!(value(&values, "db.password").as_deref(), Some("file"));
+    assert!(values.get("db.password").unwrap().secret);
+    assert!(!values.get("db.url").unwrap().secret);
+    assert_eq!(value(&values, "region").as_deref(), Some("west"));
+    assert_eq!(value(&values, ".hidden"), None);
+    assert_eq!(value(&values, "invalid"), None);
+    assert_eq!(value(&values, "missing"), None);
+}
+
+#[test]
+fn test_secrets_are_redacted() {
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path();
+    fs::write(dir.join("token"), "s3cr3t\r\n").unwrap();
+
+    let values = ConfigValues::load(&[
+        ConfigSource::Values(vec![("user".to_string(), "admin".to_string())]),
+        ConfigSource::SecretsDir(dir.to_path_buf()),
+    ])
+    .unwrap();
+
+    let token = values.get("token").unwrap();
+    assert_eq!(token.value, "s3cr3t");
+    assert!(token.secret);
+    assert_eq!(token.to_string(), REDACTED);
+    assert_eq!(values.get("user").unwrap().to_string(), "admin");
+
+    assert_eq!(
+        values.redact("user admin logged in with s3cr3t"),
+        format!("user admin logged in with {}", REDACTED)
+    );
+}
+
+#[test]
+fn test_invalid_sources() {
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path();
+    let file = dir.join("config.toml");
+    fs::write(&file, "hosts = [\"a\", \"b\"]\n").unwrap();
+
+    assert!(ConfigValues::load(&[ConfigSource::File(file)]).is_err());
+    assert!(ConfigValues::load(&[ConfigSource::File(dir.join("missing.toml"))]).is_err());
+    assert!(ConfigValues::load(&[ConfigSource::Env {
+        prefix: String::new()
+    }])
+    .is_err());
+}
+
+#[test]
+fn test_reload() {
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path();
+    let file = dir.join("config.toml");
+    fs::write(&file, "greeting = \"hello\"\n").unwrap();
+
+    let config = RuntimeConfig::new(vec![ConfigSource::File(file.clone())]).unwrap();
+    let before = config.values();
+    assert_eq!(value(&before, "greeting").as_deref(), Some("hello"));
+
+    fs::write(&file, "greeting = \"bonjour\"\n").unwrap();
+    assert!(config.reload());
+    assert_eq!(
+        value(&config.values(), "greeting").as_deref(),
+        Some("bonjour")
+    );
+    // Values already handed out do not change.
+    assert_eq!(value(&before, "greeting").as_deref(), Some("hello"));
+
+    // Invalid changes keep the previous values.
+    fs::write(&file, "greeting = ").unwrap();
+    assert!(!config.reload());
+    assert_eq!(
+        value(&config.values(), "greeting").as_deref(),
+        Some("bonjour")
+    );
+}
+
+#[test]
+fn test_watch_secrets_dir() {
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path();
+    fs::write(dir.join("token"), "first").unwrap();
+
+    let config = RuntimeConfig::new(vec![ConfigSource::SecretsDir(dir.to_path_buf())]).unwrap();
+    config.watch(Duration::from_millis(10));
+
+    // Changing a file does not change the modification time of the directory.
+    fs::write(dir.join("token"), "second").unwrap();
+    wait_for(|| value(&config.values(), "token").as_deref() == Some("second"));
+
+    fs::write(dir.join("user"), "admin").unwrap();
+    wait_for(|| value(&config.values(), "user").is_some());
+}
+
+fn wait_for(condition: impl Fn() -> bool) {
+    let start = Instant::now();
+    while !condition() {
+        assert!(start.elapsed() < Duration::from_secs(5), "timed out");
+        std::thread::sleep(Duration::from_millis(10));
+    }
+}
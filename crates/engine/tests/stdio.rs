@@ -0,0 +1,26 @@
+use glass_engine::{StdioPolicy, DEFAULT_CAPTURE_BYTES};
+
+#[test]
+fn test_parse_stdio_policy() {
+    assert_eq!(
+        "inherit".parse::<StdioPolicy>().unwrap(),
+        StdioPolicy::Inherit
+    );
+    assert_eq!(
+        "discard".parse::<StdioPolicy>().unwrap(),
+        StdioPolicy::Discard
+    );
+    assert_eq!(
+        "capture".parse::<StdioPolicy>().unwrap(),
+        StdioPolicy::Capture {
+            max_bytes: DEFAULT_CAPTURE_BYTES
+        }
+    );
+    assert_eq!(
+        "capture:16".parse::<StdioPolicy>().unwrap(),
+        StdioPolicy::Capture { max_bytes: 16 }
+    );
+
+    assert!("capture:lots".parse::<StdioPolicy>().is_err());
+    assert!("file".parse::<StdioPolicy>().is_err());
+}
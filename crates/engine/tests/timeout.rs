@@ -0,0 +1,69 @@
+use glass_engine::{Config, ExecutionError, WasiExecutionContextBuilder};
+use std::time::Duration;
+
+const MODULE: &str = r#"
+(module
+    (func (export "spin") (loop (br 0)))
+    (func (export "answer") (result i32) i32.const 42))
+"#;
+
+const SPINNING_START_MODULE: &str = r#"
+(module
+    (func $spin (loop (br 0)))
+    (start $spin))
+"#;
+
+const TIMEOUT: Duration = Duration::from_millis(100);
+
+fn config() -> Config {
+    Config {
+        timeout: Some(TIMEOUT),
+        ..Config::default()
+    }
+}
+
+#[test]
+fn test_timeout_recovers() {
+    let ctx = WasiExecutionContextBuilder::<()>::new(&config())
+        .unwrap()
+        .build_from_bytes("spin", MODULE.as_bytes())
+        .unwrap();
+
+    // The watchdog keeps interrupting spinning instances, and later
+    // invocations are not affected by the earlier deadlines.
+    for _ in 0..2 {
+        let (mut store, instance) = ctx.prepare_exec(None).unwrap();
+        let spin = instance
+            .get_typed_func::<(), (), _>(&mut store, "spin")
+            .unwrap();
+        let err = ctx
+            .call(&mut store, |store| Ok(spin.call(store, ())?))
+            .unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<ExecutionError>(),
+            Some(&ExecutionError::Timeout { timeout: TIMEOUT })
+        );
+
+        let (mut store, instance) = ctx.prepare_exec(None).unwrap();
+        let answer = instance
+            .get_typed_func::<(), i32, _>(&mut store, "answer")
+            .unwrap();
+        std::thread::sleep(TIMEOUT * 2);
+        let res = ctx.call(&mut store, |store| Ok(answer.call(store, ())?));
+        assert_eq!(res.unwrap(), 42);
+    }
+}
+
+#[test]
+fn test_timeout_in_start_function() {
+    let ctx = WasiExecutionContextBuilder::<()>::new(&config())
+        .unwrap()
+        .build_from_bytes("spin", SPINNING_START_MODULE.as_bytes())
+        .unwrap();
+
+    let err = ctx.prepare_exec(None).unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<ExecutionError>(),
+        Some(&ExecutionError::Timeout { timeout: TIMEOUT })
+    );
+}
@@ -0,0 +1,27 @@
+use anyhow::Error;
+use glass_engine::{precompile_module, Config, ModuleSource};
+use structopt::{clap::AppSettings, StructOpt};
+
+#[derive(StructOpt, Debug)]
+#[structopt(
+    about = "Compile the entrypoint component ahead of time",
+    global_settings = &[AppSettings::ColoredHelp, AppSettings::ArgRequiredElseHelp]
+)]
+pub struct CompileCmd {
+    #[structopt(
+        short = "o",
+        long = "output",
+        help = "Path to write the precompiled component to"
+    )]
+    pub output: String,
+}
+
+impl CompileCmd {
+    pub async fn run(&self, module: &ModuleSource, config: &Config) -> Result<(), Error> {
+        let bytes = module.read()?;
+        std::fs::write(&self.output, precompile_module(config, &bytes)?)?;
+
+        log::info!("Wrote precompiled component to {}", self.output);
+        Ok(())
+    }
+}
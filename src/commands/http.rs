@@ -1,9 +1,14 @@
-use anyhow::Error;
-use glass_engine::{Config, WasiExecutionContextBuilder};
-use glass_http::{Engine, Trigger};
-use std::sync::Arc;
+use anyhow::{anyhow, Error};
+use glass_engine::{Config, ModuleSource, Reloadable};
+use glass_http::{interfaces, BoxedEngine, RoutePattern, Router, TlsConfig, Trigger};
+use ipnet::IpNet;
+use serde::Deserialize;
+use std::{collections::HashMap, net::IpAddr, path::PathBuf, str::FromStr, time::Duration};
 use structopt::{clap::AppSettings, StructOpt};
 
+/// How often the module is checked for changes when hot reload is enabled.
+const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
 #[derive(StructOpt, Debug)]
 #[structopt(
     about = "Start the default HTTP listener",
@@ -20,21 +25,263 @@ pub struct HttpCmd {
     #[structopt(
         long = "interface",
         default_value = "deislabs_http_v01",
-        help = "WASI interface the entrypoint component implements"
+        help = "WASI interface the entrypoint component implements, such as `deislabs_http_v02` or `wagi`, or `auto` to detect it"
     )]
     pub interface: String,
+
+    #[structopt(
+        long = "hot-reload",
+        help = "Reload the component when the local file changes or on SIGHUP"
+    )]
+    pub hot_reload: bool,
+
+    #[structopt(
+        long = "problem-details",
+        help = "Send RFC 7807 problem details in the body of error responses"
+    )]
+    pub problem_details: bool,
+
+    #[structopt(
+        long = "inherit-stdin",
+        help = "Let guests read the standard input of the server instead of an empty one, except for WAGI"
+    )]
+    pub inherit_stdin: bool,
+
+    #[structopt(
+        long = "tls-cert",
+        value_name = "FILE",
+        parse(from_os_str),
+        requires = "tls-key",
+        help = "PEM certificate chain to serve HTTPS with, reloaded when it changes"
+    )]
+    pub tls_cert: Option<PathBuf>,
+
+    #[structopt(
+        long = "tls-key",
+        value_name = "FILE",
+        parse(from_os_str),
+        requires = "tls-cert",
+        help = "PEM private key of the TLS certificate"
+    )]
+    pub tls_key: Option<PathBuf>,
+
+    #[structopt(
+        long = "tls-client-ca",
+        value_name = "FILE",
+        parse(from_os_str),
+        requires = "tls-cert",
+        help = "PEM bundle of CAs that client certificates must be signed by"
+    )]
+    pub tls_client_ca: Option<PathBuf>,
+
+    #[structopt(
+        long = "trusted-proxy",
+        value_name = "IP[/PREFIX]",
+        number_of_values = 1,
+        parse(try_from_str = parse_trusted_proxy),
+        help = "Proxy trusted to report the client address in X-Forwarded-For"
+    )]
+    pub trusted_proxies: Vec<IpNet>,
+
+    #[structopt(
+        long = "max-request-body-bytes",
+        value_name = "BYTES",
+        help = "Answer requests with larger bodies with 413, except for streaming interfaces"
+    )]
+    pub max_request_body_bytes: Option<usize>,
+
+    #[structopt(
+        long = "route",
+        value_name = "PATTERN[=MODULE]",
+        number_of_values = 1,
+        help = "Serve paths matching a pattern such as `/users/:id` or `/api/*` with a module, or with the entrypoint if none is given"
+    )]
+    pub routes: Vec<RouteSpec>,
+
+    #[structopt(
+        long = "routes-file",
+        value_name = "FILE",
+        parse(from_os_str),
+        help = "TOML file with a `[[route]]` table per route and an optional `not_found` module"
+    )]
+    pub routes_file: Option<PathBuf>,
+
+    #[structopt(
+        long = "not-found",
+        value_name = "MODULE",
+        help = "Module serving requests that match no route, instead of an empty 404"
+    )]
+    pub not_found: Option<String>,
 }
 
-impl HttpCmd {
-    pub async fn run(&self, module: &str, config: &Config) -> Result<(), Error> {
-        let engine = Engine(Arc::new(
-            WasiExecutionContextBuilder::new(config)?.build(&module)?,
-        ));
+/// A route from the command line or the routes file.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RouteSpec {
+    pub path: String,
+    /// Path of the module serving the route, defaulting to the entrypoint.
+    pub module: Option<String>,
+    /// Interface of the module, defaulting to the one set by `--interface`.
+    pub interface: Option<String>,
+}
+
+impl FromStr for RouteSpec {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.splitn(2, '=');
+        let path = parts.next().unwrap_or_default().to_string();
+        let module = parts.next().map(|m| m.to_string());
+        anyhow::ensure!(
+            module.as_deref() != Some(""),
+            "route '{}' has an empty module",
+            s
+        );
+
+        Ok(Self {
+            path,
+            module,
+            interface: None,
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RoutesFile {
+    #[serde(default)]
+    route: Vec<RouteSpec>,
+    not_found: Option<String>,
+}
 
+impl HttpCmd {
+    pub async fn run(&self, module: Option<&ModuleSource>, config: &Config) -> Result<(), Error> {
         let trigger = Trigger {
             address: self.address.clone(),
+            timeout: config.timeout,
+            problem_details: self.problem_details,
+            tls: self.tls(),
+            trusted_proxies: self.trusted_proxies.clone(),
+            max_request_body_bytes: self.max_request_body_bytes,
+        };
+
+        // Requests are served concurrently, so by default guests do not
+        // compete for the standard input of the server.
+        let mut config = config.clone();
+        config.empty_stdin = !self.inherit_stdin;
+
+        let mut routes = self.routes.clone();
+        let mut not_found = self.not_found.clone();
+        if let Some(path) = &self.routes_file {
+            let file: RoutesFile = toml::from_str(&std::fs::read_to_string(path)?)
+                .map_err(|e| anyhow!("cannot parse routes file {}: {}", path.display(), e))?;
+            routes.extend(file.route);
+            not_found = not_found.or(file.not_found);
+        }
+        if routes.is_empty() {
+            routes.push("/*".parse()?);
+        }
+
+        // Routes served by the same module with the same interface share an engine.
+        let mut engines: HashMap<(String, String), BoxedEngine> = HashMap::new();
+        let mut engine = |module: ModuleSource, interface: &str| -> Result<BoxedEngine, Error> {
+            let key = (module.name().to_string(), interface.to_string());
+            if let Some(engine) = engines.get(&key) {
+                return Ok(engine.clone());
+            }
+            let engine = self.build_engine(module, interface, &config)?;
+            engines.insert(key, engine.clone());
+            Ok(engine)
         };
 
-        trigger.run(engine).await
+        let mut router = Router::new();
+        for route in routes {
+            let pattern: RoutePattern = route.path.parse()?;
+            let module = match route.module {
+                Some(path) => ModuleSource::File(path),
+                None => module.cloned().ok_or_else(|| {
+                    anyhow!("route '{}' has no module and no entrypoint is set", pattern)
+                })?,
+            };
+            log::info!("Serving {} with {}", pattern, module);
+
+            let interface = route.interface.as_deref().unwrap_or(&self.interface);
+            router = router.route(pattern, engine(module, interface)?);
+        }
+        if let Some(path) = not_found {
+            router = router.not_found(engine(ModuleSource::File(path), &self.interface)?);
+        }
+
+        trigger.run(router).await
     }
+
+    fn tls(&self) -> Option<TlsConfig> {
+        match (&self.tls_cert, &self.tls_key) {
+            (Some(cert), Some(key)) => Some(TlsConfig {
+                cert: cert.clone(),
+                key: key.clone(),
+                client_ca: self.tls_client_ca.clone(),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Build the engine for a module, reloading it on changes if hot
+    /// reload is enabled.
+    fn build_engine(
+        &self,
+        module: ModuleSource,
+        interface: &str,
+        config: &Config,
+    ) -> Result<BoxedEngine, Error> {
+        let build = {
+            let (interface, module, config) =
+                (interface.to_string(), module.clone(), config.clone());
+            move || interfaces::registry().build(&interface, &module, &config)
+        };
+
+        if !self.hot_reload {
+            return build();
+        }
+
+        let engine = Reloadable::new(build)?;
+        if let ModuleSource::File(path) = module {
+            engine.watch(path, WATCH_INTERVAL);
+        }
+        reload_on_hangup(engine.clone())?;
+
+        Ok(BoxedEngine::new(engine))
+    }
+}
+
+/// Parse a network, or a single address as the network containing only it.
+fn parse_trusted_proxy(s: &str) -> Result<IpNet, Error> {
+    match s.parse::<IpNet>() {
+        Ok(net) => Ok(net),
+        Err(_) => Ok(IpNet::from(s.parse::<IpAddr>()?)),
+    }
+}
+
+/// Reload the engine in the background whenever the process receives SIGHUP.
+#[cfg(unix)]
+pub fn reload_on_hangup<E: Clone + Send + Sync + 'static>(
+    engine: Reloadable<E>,
+) -> Result<(), Error> {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut hangup = signal(SignalKind::hangup())?;
+    tokio::spawn(async move {
+        while hangup.recv().await.is_some() {
+            log::info!("Received SIGHUP, reloading");
+            let engine = engine.clone();
+            let _ = tokio::task::spawn_blocking(move || engine.reload()).await;
+        }
+    });
+
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn reload_on_hangup<E>(_: Reloadable<E>) -> Result<(), Error> {
+    Ok(())
 }
@@ -1,2 +1,3 @@
+pub mod compile;
 pub mod http;
 pub mod ping;
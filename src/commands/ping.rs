@@ -1,7 +1,6 @@
 use anyhow::Error;
-use glass_engine::{Config, WasiExecutionContextBuilder};
-use glass_ping::{PingEngine, TimerTrigger};
-use std::sync::Arc;
+use glass_engine::{Config, ModuleSource};
+use glass_ping::{MessageTrigger, TimerTrigger};
 use structopt::{clap::AppSettings, StructOpt};
 
 #[derive(StructOpt, Debug)]
@@ -13,7 +12,7 @@ pub struct PingCmd {
     #[structopt(
         long = "interface",
         default_value = "deislabs_ping_v01",
-        help = "WASI interface the entrypoint component implements"
+        help = "WASI interface the entrypoint component implements, or `auto` to detect it"
     )]
     pub interface: String,
 
@@ -23,16 +22,31 @@ pub struct PingCmd {
         help = "Interval in seconds"
     )]
     pub interval_seconds: u64,
+
+    #[structopt(
+        long = "topic",
+        value_name = "TOPIC",
+        help = "Execute the component for each message guests publish to TOPIC with `--publish channel`, instead of on a timer"
+    )]
+    pub topic: Option<String>,
 }
 
 impl PingCmd {
-    pub async fn run(&self, module: &str, config: &Config) -> Result<(), Error> {
-        let engine = PingEngine(Arc::new(
-            WasiExecutionContextBuilder::new(&config)?.build(&module)?,
-        ));
+    pub async fn run(&self, module: &ModuleSource, config: &Config) -> Result<(), Error> {
+        let engine = glass_ping::registry().build(&self.interface, module, config)?;
+
+        if let Some(topic) = &self.topic {
+            let publisher = config
+                .publisher
+                .as_ref()
+                .ok_or_else(|| anyhow::anyhow!("--topic requires --publish channel"))?;
+            let trigger = MessageTrigger::subscribe(publisher.as_ref(), topic, config.timeout)?;
+            return trigger.run(engine).await;
+        }
 
         let trigger = TimerTrigger {
             interval: std::time::Duration::from_secs(self.interval_seconds),
+            timeout: config.timeout,
         };
 
         trigger.run(engine).await
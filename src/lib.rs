@@ -1,3 +1,3 @@
 pub mod commands;
 
-pub use commands::{http::HttpCmd, ping::PingCmd};
+pub use commands::{compile::CompileCmd, http::HttpCmd, ping::PingCmd};
@@ -1,8 +1,16 @@
-use anyhow::{bail, Error};
-use glass::{HttpCmd, PingCmd};
-use glass_engine::Config;
+use anyhow::{anyhow, bail, Error};
+use glass::{CompileCmd, HttpCmd, PingCmd};
+use glass_engine::{
+    source::{bindle, oci},
+    Config, ConfigSource, KvBackend, ModuleSource, PoolingConfig, PublishBackend, RuntimeConfig,
+    SqliteDatabase, StdioPolicy,
+};
+use std::{path::PathBuf, sync::Arc, time::Duration};
 use structopt::{clap::AppSettings, StructOpt};
 
+/// How often config files and secrets directories are checked for changes.
+const CONFIG_WATCH_INTERVAL: Duration = Duration::from_secs(10);
+
 #[tokio::main]
 pub async fn main() -> Result<(), Error> {
     env_logger::init();
@@ -13,12 +21,111 @@ pub async fn main() -> Result<(), Error> {
 impl Opt {
     pub async fn run(&self) -> Result<(), Error> {
         let dirs = compute_preopen_dirs(self.dirs.clone(), self.map_dirs.clone())?;
-        let config = Config::new(self.vars.clone(), dirs, self.allowed_hosts.clone());
+        let mut config = Config::new(self.vars.clone(), dirs, self.allowed_hosts.clone());
+        config.max_fuel = self.max_fuel;
+        config.timeout = self.timeout_ms.map(Duration::from_millis);
+        config.resource_limits.max_memory_bytes = self.max_memory_bytes;
+        config.resource_limits.max_instances = self.max_instances;
+        config.resource_limits.max_tables = self.max_tables;
+        config.cache_dir = self.cache_dir.clone();
+        config.allow_precompiled = self.allow_precompiled;
+        config.stdout = self.stdout.clone();
+        config.stderr = self.stderr.clone();
+        config.kv_store = self.kv.as_ref().map(KvBackend::open).transpose()?;
+        config.kv_namespace = self.kv_namespace.clone();
+        config.sqlite_databases = self.sqlite.clone();
+        for db in &self.sqlite_read_only {
+            config.sqlite_databases.push(SqliteDatabase {
+                read_only: true,
+                ..db.clone()
+            });
+        }
+        config.sqlite_timeout = self.sqlite_timeout_ms.map(Duration::from_millis);
+        config.runtime_config = self.runtime_config()?;
+        config.publisher = self
+            .publish
+            .as_ref()
+            .map(PublishBackend::open)
+            .transpose()?;
+        if self.pooling {
+            config.pooling = Some(PoolingConfig::default());
+        }
+
+        let module = self.fetch_module(&mut config).await?;
+        let entrypoint = || {
+            module
+                .as_ref()
+                .ok_or_else(|| anyhow!("one of --local, --bindle or --oci must be set"))
+        };
 
         match &self.cmd {
-            SubCommand::Http(h) => h.run(&self.module, &config).await,
-            SubCommand::Ping(p) => p.run(&self.module, &config).await,
+            SubCommand::Compile(c) => c.run(entrypoint()?, &config).await,
+            SubCommand::Http(h) => h.run(module.as_ref(), &config).await,
+            SubCommand::Ping(p) => p.run(entrypoint()?, &config).await,
+        }
+    }
+
+    /// Get the configuration guests read at runtime, if any source is set.
+    /// Sources are listed from the highest precedence to the lowest.
+    fn runtime_config(&self) -> Result<Option<RuntimeConfig>, Error> {
+        let mut sources = Vec::new();
+        if !self.config_values.is_empty() {
+            sources.push(ConfigSource::Values(self.config_values.clone()));
+        }
+        if let Some(path) = &self.config_file {
+            sources.push(ConfigSource::File(path.clone()));
         }
+        if let Some(dir) = &self.secrets_dir {
+            sources.push(ConfigSource::SecretsDir(dir.clone()));
+        }
+        if let Some(prefix) = &self.config_env_prefix {
+            sources.push(ConfigSource::Env {
+                prefix: prefix.clone(),
+            });
+        }
+        if sources.is_empty() {
+            return Ok(None);
+        }
+
+        let runtime_config = RuntimeConfig::new(sources)?;
+        runtime_config.watch(CONFIG_WATCH_INTERVAL);
+        Ok(Some(runtime_config))
+    }
+
+    /// Get the entrypoint module, fetching it first if it is not a
+    /// local file. Assets that come with the module are preopened.
+    async fn fetch_module(&self, config: &mut Config) -> Result<Option<ModuleSource>, Error> {
+        if let Some(module) = &self.module {
+            return Ok(Some(ModuleSource::File(module.clone())));
+        }
+
+        let cache_dir = config
+            .cache_dir
+            .clone()
+            .unwrap_or_else(|| std::env::temp_dir().join("glass"));
+
+        if let Some(reference) = &self.oci {
+            let bytes = oci::pull(reference, &cache_dir.join("oci"), self.oci_insecure).await?;
+            return Ok(Some(ModuleSource::Bytes {
+                name: reference.clone(),
+                bytes: Arc::new(bytes),
+            }));
+        }
+
+        let (id, server) = match (&self.bindle, &self.bindle_server) {
+            (Some(id), Some(server)) => (id, server),
+            _ => return Ok(None),
+        };
+        let bindle = bindle::fetch(server, id, &cache_dir.join("bindle")).await?;
+        if let Some(assets) = bindle.assets {
+            config
+                .preopen_dirs
+                .push(("/".to_string(), assets.to_string_lossy().into_owned()));
+        }
+
+        Ok(Some(ModuleSource::File(
+            bindle.entrypoint.to_string_lossy().into_owned(),
+        )))
     }
 }
 
@@ -56,6 +163,167 @@ pub struct Opt {
     )]
     map_dirs: Vec<(String, String)>,
 
+    #[structopt(
+        long = "max-fuel",
+        global = true,
+        value_name = "FUEL",
+        help = "Maximum amount of fuel a single invocation of the guest can consume"
+    )]
+    max_fuel: Option<u64>,
+
+    #[structopt(
+        long = "timeout-ms",
+        global = true,
+        value_name = "MILLISECONDS",
+        help = "Wall-clock deadline for a single invocation of the guest"
+    )]
+    timeout_ms: Option<u64>,
+
+    #[structopt(
+        long = "max-memory-bytes",
+        global = true,
+        value_name = "BYTES",
+        help = "Maximum size of any linear memory of the guest"
+    )]
+    max_memory_bytes: Option<usize>,
+
+    #[structopt(
+        long = "max-instances",
+        global = true,
+        value_name = "COUNT",
+        help = "Maximum number of instances a guest can create, including its own"
+    )]
+    max_instances: Option<usize>,
+
+    #[structopt(
+        long = "max-tables",
+        global = true,
+        value_name = "COUNT",
+        help = "Maximum number of tables a guest can create"
+    )]
+    max_tables: Option<usize>,
+
+    #[structopt(
+        long = "pooling",
+        global = true,
+        help = "Allocate instances from a pre-allocated pool instead of on demand"
+    )]
+    pooling: bool,
+
+    #[structopt(
+        long = "cache-dir",
+        global = true,
+        value_name = "DIRECTORY",
+        parse(from_os_str),
+        help = "Directory where compiled modules are cached across runs"
+    )]
+    cache_dir: Option<PathBuf>,
+
+    #[structopt(
+        long = "stdout",
+        global = true,
+        value_name = "POLICY",
+        default_value = "inherit",
+        help = "What happens to the guest's stdout: `inherit`, `discard`, `capture` to the log, or `capture:<max bytes>`"
+    )]
+    stdout: StdioPolicy,
+
+    #[structopt(
+        long = "stderr",
+        global = true,
+        value_name = "POLICY",
+        default_value = "inherit",
+        help = "What happens to the guest's stderr: `inherit`, `discard`, `capture` to the log, or `capture:<max bytes>`"
+    )]
+    stderr: StdioPolicy,
+
+    #[structopt(
+        long = "kv",
+        global = true,
+        value_name = "BACKEND",
+        help = "Key-value store guests can use: `memory`, `sqlite:<path>` or a `redis://` URL"
+    )]
+    kv: Option<KvBackend>,
+
+    #[structopt(
+        long = "kv-namespace",
+        global = true,
+        value_name = "NAMESPACE",
+        requires = "kv",
+        help = "Key-value namespace shared by all modules, instead of one namespace per module"
+    )]
+    kv_namespace: Option<String>,
+
+    #[structopt(
+        long = "sqlite",
+        global = true,
+        number_of_values = 1,
+        value_name = "NAME=PATH",
+        help = "SQLite database guests can open as NAME"
+    )]
+    sqlite: Vec<SqliteDatabase>,
+
+    #[structopt(
+        long = "sqlite-read-only",
+        global = true,
+        number_of_values = 1,
+        value_name = "NAME=PATH",
+        help = "SQLite database guests can open as NAME, without writing to it"
+    )]
+    sqlite_read_only: Vec<SqliteDatabase>,
+
+    #[structopt(
+        long = "sqlite-timeout-ms",
+        global = true,
+        value_name = "MILLISECONDS",
+        help = "Deadline for a single SQLite statement run by the guest"
+    )]
+    sqlite_timeout_ms: Option<u64>,
+
+    #[structopt(
+        long = "config",
+        global = true,
+        number_of_values = 1,
+        value_name = "KEY=VAL",
+        parse(try_from_str = parse_env_var),
+        help = "Set a configuration value the guest can read at runtime"
+    )]
+    config_values: Vec<(String, String)>,
+
+    #[structopt(
+        long = "config-file",
+        global = true,
+        value_name = "FILE",
+        parse(from_os_str),
+        help = "TOML file of configuration values the guest can read at runtime, reloaded when it changes"
+    )]
+    config_file: Option<PathBuf>,
+
+    #[structopt(
+        long = "secrets-dir",
+        global = true,
+        value_name = "DIRECTORY",
+        parse(from_os_str),
+        help = "Directory of secret files the guest can read as configuration, redacted from the log"
+    )]
+    secrets_dir: Option<PathBuf>,
+
+    #[structopt(
+        long = "config-env-prefix",
+        global = true,
+        value_name = "PREFIX",
+        help = "Expose host environment variables starting with PREFIX as configuration"
+    )]
+    config_env_prefix: Option<String>,
+
+    #[structopt(
+        long = "publish",
+        global = true,
+        value_name = "BACKEND",
+        help = "Where messages the guest publishes go: `file:<path>` as JSON lines, `channel` for `glass ping --topic`, or a `nats://` or `mqtt://` URL"
+    )]
+    publish: Option<PublishBackend>,
+
     #[structopt(
         short = "a",
         long = "allowed-host",
@@ -64,8 +332,54 @@ pub struct Opt {
     )]
     allowed_hosts: Option<Vec<String>>,
 
-    #[structopt(long = "local", global = true, help = "Path to local WASI component")]
-    pub module: String,
+    #[structopt(
+        long = "local",
+        global = true,
+        conflicts_with_all = &["bindle", "oci"],
+        help = "Path to local WASI component, or to a component precompiled by `glass compile` with --allow-precompiled"
+    )]
+    pub module: Option<String>,
+
+    #[structopt(
+        long = "allow-precompiled",
+        global = true,
+        requires = "module",
+        help = "Allow the local component to be precompiled by `glass compile`. Precompiled components contain native code that is not validated, so only use ones you built"
+    )]
+    pub allow_precompiled: bool,
+
+    #[structopt(
+        long = "bindle",
+        global = true,
+        value_name = "NAME/VERSION",
+        requires = "bindle-server",
+        help = "Bindle containing the WASI component"
+    )]
+    pub bindle: Option<String>,
+
+    #[structopt(
+        long = "bindle-server",
+        global = true,
+        value_name = "URL",
+        help = "URL of the Bindle server to fetch the bindle from"
+    )]
+    pub bindle_server: Option<String>,
+
+    #[structopt(
+        long = "oci",
+        global = true,
+        value_name = "REFERENCE",
+        conflicts_with = "bindle",
+        help = "OCI reference of the WASI component, e.g. oci://registry/repository:tag"
+    )]
+    pub oci: Option<String>,
+
+    #[structopt(
+        long = "oci-insecure",
+        global = true,
+        help = "Pull from the OCI registry over plain HTTP"
+    )]
+    pub oci_insecure: bool,
 
     #[structopt(subcommand)]
     pub cmd: SubCommand,
@@ -73,6 +387,7 @@ pub struct Opt {
 
 #[derive(StructOpt, Debug)]
 pub enum SubCommand {
+    Compile(CompileCmd),
     Http(HttpCmd),
     Ping(PingCmd),
 }
@@ -0,0 +1,32 @@
+#![cfg(unix)]
+
+use glass::commands::http::reload_on_hangup;
+use glass_engine::Reloadable;
+use std::{
+    process::Command,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+#[tokio::test]
+async fn test_reload_on_hangup() {
+    let builds = Arc::new(AtomicUsize::new(0));
+    let counter = builds.clone();
+    let engine = Reloadable::new(move || Ok(counter.fetch_add(1, Ordering::SeqCst))).unwrap();
+    reload_on_hangup(engine.clone()).unwrap();
+
+    let pid = std::process::id().to_string();
+    let status = Command::new("kill").args(&["-HUP", &pid]).status().unwrap();
+    assert!(status.success());
+
+    for _ in 0..100 {
+        if engine.current() == 1 {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    panic!("the engine was not reloaded");
+}